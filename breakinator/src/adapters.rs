@@ -0,0 +1,77 @@
+use crate::refseq::revcomp;
+
+// adapter and barcode sequences searched for in the unaligned read sequence between segments
+pub struct Adapters {
+    //name with the sequence on both strands
+    seqs: Vec<(String, Vec<u8>, Vec<u8>)>,
+}
+
+impl Adapters {
+    //adapters are given as NAME=SEQ, the sequence is used as name when no name is given
+    pub fn load(specs: &[String]) -> Adapters {
+        let seqs = specs.iter().map(|spec| {
+            let (name, seq) = spec.split_once('=').unwrap_or((spec.as_str(), spec.as_str()));
+            let seq = seq.to_ascii_uppercase().into_bytes();
+            (name.to_string(), revcomp(&seq), seq)
+        }).collect();
+        Adapters{seqs}
+    }
+
+    //first adapter found in the query on either strand with at most max_mismatches substitutions
+    pub fn find(&self, query: &[u8], max_mismatches: u32) -> Option<&str> {
+        for (name, rc, seq) in &self.seqs {
+            if contains_approx(query, seq, max_mismatches) || contains_approx(query, rc, max_mismatches) {
+                return Some(name);
+            }
+        }
+        None
+    }
+}
+
+fn contains_approx(query: &[u8], pattern: &[u8], max_mismatches: u32) -> bool {
+    if pattern.is_empty() || query.len() < pattern.len() {
+        return false;
+    }
+    query.windows(pattern.len()).any(|w| {
+        let mut mismatches = 0;
+        for (a, b) in w.iter().zip(pattern) {
+            if a != b {
+                mismatches += 1;
+                if mismatches > max_mismatches {
+                    return false;
+                }
+            }
+        }
+        true
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Adapters, contains_approx};
+
+    #[test]
+    fn approximate_matches() {
+        assert!(contains_approx(b"TTTTACGTACGTTTTT", b"ACGTACGT", 0));
+        //matches at either end of the query
+        assert!(contains_approx(b"ACGTACGTTT", b"ACGTACGT", 0));
+        assert!(contains_approx(b"TTACGTACGT", b"ACGTACGT", 0));
+        assert!(contains_approx(b"ACGTACGT", b"ACGTACGT", 0));
+        //substitutions up to the limit
+        assert!(contains_approx(b"TTACCTACGATT", b"ACGTACGT", 2));
+        assert!(!contains_approx(b"TTACCTACGATT", b"ACGTACGT", 1));
+        //a pattern cut off at the end of the query does not match
+        assert!(!contains_approx(b"TTTTACGTACG", b"ACGTACGT", 0));
+        assert!(!contains_approx(b"ACGT", b"ACGTACGT", 8));
+        assert!(!contains_approx(b"ACGT", b"", 0));
+    }
+
+    #[test]
+    fn both_strands() {
+        let adapters = Adapters::load(&[String::from("bc01=aaggttca"), String::from("CCCCGGGG")]);
+        assert_eq!(adapters.find(b"TTTTAAGGTTCATTTT", 0), Some("bc01"));
+        assert_eq!(adapters.find(b"TTTTTGAACCTTTTTT", 0), Some("bc01"));
+        assert_eq!(adapters.find(b"ATCCCCGGGGAT", 0), Some("CCCCGGGG"));
+        assert_eq!(adapters.find(b"TTTTTTTTTTTT", 2), None);
+    }
+}
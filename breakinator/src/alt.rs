@@ -0,0 +1,165 @@
+use std::{collections::HashMap, fs::File, io::{self, BufRead, BufReader}};
+use crate::Breakpoint;
+
+// placement of an ALT/HLA/decoy contig on the primary assembly
+struct AltPlacement {
+    chr: String,
+    start: i64,
+    end: i64,
+    reverse: bool,
+    //bases at the start of the ALT contig clipped from the alignment
+    clip: i64,
+}
+
+// equivalence of ALT contigs to their primary regions
+pub struct AltContigs {
+    contigs: HashMap<String, AltPlacement>,
+}
+
+impl AltContigs {
+    //read a bwa .alt file (SAM records of ALT contigs aligned to the primary assembly)
+    //or a TSV of alt_contig, primary_chr, start, end (0-based)
+    pub fn load(path: &str) -> io::Result<AltContigs> {
+        let mut contigs = HashMap::new();
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            if line.starts_with('@') || line.starts_with('#') || line.trim().is_empty() {
+                continue;
+            }
+            let fields: Vec<&str> = line.split('\t').collect();
+            //SAM records have a numeric FLAG and a CIGAR, any other line is read as the TSV
+            let is_sam = fields.len() >= 6 && fields[1].parse::<u16>().is_ok() && (fields[5] == "*" || parse_cigar(fields[5]).is_some());
+            if is_sam {
+                //unplaced ALT contigs have no primary region
+                if fields[2] == "*" || fields[5] == "*" {
+                    continue;
+                }
+                let flag = fields[1].parse::<u16>().unwrap();
+                let ops = parse_cigar(fields[5]).unwrap();
+                let start = fields[3].parse::<i64>().expect("invalid POS in .alt file") - 1;
+                let ref_len: i64 = ops.iter().filter(|(_, op)| matches!(op, 'M' | 'D' | 'N' | '=' | 'X')).map(|(l, _)| l).sum();
+                //the CIGAR is in primary orientation, so the start of a reverse strand ALT contig is clipped at the end
+                let reverse = flag & 16 != 0;
+                let clip_op = if reverse { ops.last() } else { ops.first() };
+                let clip = clip_op.filter(|(_, op)| matches!(op, 'S' | 'H')).map_or(0, |(l, _)| *l);
+                contigs.insert(fields[0].to_string(), AltPlacement{chr: fields[2].to_string(), start,
+                    end: start + ref_len, reverse, clip});
+            } else if fields.len() >= 4 {
+                contigs.insert(fields[0].to_string(), AltPlacement{chr: fields[1].to_string(),
+                    start: fields[2].parse::<i64>().expect("invalid start in alt mapping"),
+                    end: fields[3].parse::<i64>().expect("invalid end in alt mapping"), reverse: false, clip: 0});
+            } else {
+                eprintln!("Warning: skipping malformed alt mapping line: {}", line);
+            }
+        }
+        Ok(AltContigs{contigs})
+    }
+
+    //approximate position and direction of an ALT contig end on the primary assembly
+    fn lift(&self, chr: &str, loc: i64, dir: char) -> Option<(String, i64, char)> {
+        let p = self.contigs.get(chr)?;
+        if p.reverse {
+            let flipped = if dir == '>' { '<' } else { '>' };
+            Some((p.chr.clone(), (p.end - (loc - p.clip)).clamp(p.start, p.end), flipped))
+        } else {
+            Some((p.chr.clone(), (p.start + (loc - p.clip)).clamp(p.start, p.end), dir))
+        }
+    }
+
+    //inter-contig breakpoint with ALT ends lifted onto the primary contig of the other end,
+    //None when the junction does not join an ALT contig to its primary region
+    pub fn lift_break(&self, brk: &Breakpoint) -> Option<Breakpoint> {
+        if brk.b1_chr == brk.b2_chr {
+            return None;
+        }
+        let dirs: Vec<char> = brk.directions.chars().collect();
+        let (chr1, loc1, dir1) = self.lift(&brk.b1_chr, brk.b1_loc, dirs[0]).unwrap_or((brk.b1_chr.clone(), brk.b1_loc, dirs[0]));
+        let (chr2, loc2, dir2) = self.lift(&brk.b2_chr, brk.b2_loc, dirs[1]).unwrap_or((brk.b2_chr.clone(), brk.b2_loc, dirs[1]));
+        if chr1 != chr2 {
+            return None;
+        }
+        let mut lifted = brk.clone();
+        lifted.b1_chr = chr1;
+        lifted.b1_loc = loc1;
+        lifted.b2_chr = chr2;
+        lifted.b2_loc = loc2;
+        lifted.directions = format!("{}{}", dir1, dir2);
+        Some(lifted)
+    }
+}
+
+//length and operation of each CIGAR element, None when the string is not a CIGAR
+fn parse_cigar(cigar: &str) -> Option<Vec<(i64, char)>> {
+    let mut ops = Vec::new();
+    let mut num = String::new();
+    for c in cigar.chars() {
+        if c.is_ascii_digit() {
+            num.push(c);
+        } else if "MIDNSHP=X".contains(c) && !num.is_empty() {
+            ops.push((num.parse::<i64>().ok()?, c));
+            num.clear();
+        } else {
+            return None;
+        }
+    }
+    if ops.is_empty() || !num.is_empty() {
+        return None;
+    }
+    Some(ops)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, fs};
+    use super::{AltContigs, AltPlacement};
+    use crate::Breakpoint;
+
+    fn placement(chr: &str, start: i64, end: i64, reverse: bool, clip: i64) -> AltPlacement {
+        AltPlacement{chr: chr.to_string(), start, end, reverse, clip}
+    }
+
+    #[test]
+    fn lift() {
+        let alt = AltContigs{contigs: HashMap::from([
+            (String::from("chr6_fwd_alt"), placement("chr6", 1000, 2000, false, 100)),
+            (String::from("chr6_rev_alt"), placement("chr6", 3000, 4000, true, 10)),
+        ])};
+        //positions past the leading clip map from the start of the placement, or back from its end on the reverse strand
+        assert_eq!(alt.lift("chr6_fwd_alt", 150, '>'), Some((String::from("chr6"), 1050, '>')));
+        assert_eq!(alt.lift("chr6_rev_alt", 110, '>'), Some((String::from("chr6"), 3900, '<')));
+        assert_eq!(alt.lift("chr6_rev_alt", 110, '<'), Some((String::from("chr6"), 3900, '>')));
+        //positions in the clip or beyond the aligned part stay within the placement
+        assert_eq!(alt.lift("chr6_fwd_alt", 50, '>'), Some((String::from("chr6"), 1000, '>')));
+        assert_eq!(alt.lift("chr6_fwd_alt", 5000, '>'), Some((String::from("chr6"), 2000, '>')));
+        assert_eq!(alt.lift("chr6", 150, '>'), None);
+
+        let brk = |chr2: &str, loc2: i64| Breakpoint{b1_chr: String::from("chr6"), b1_loc: 1500, directions: String::from(">>"),
+            b2_chr: chr2.to_string(), b2_loc: loc2, ..Default::default()};
+        let lifted = alt.lift_break(&brk("chr6_rev_alt", 510)).unwrap();
+        assert_eq!((lifted.b2_chr.as_str(), lifted.b2_loc, lifted.directions.as_str()), ("chr6", 3500, "><"));
+        //only junctions between an ALT contig and its primary contig are lifted
+        assert!(alt.lift_break(&brk("chr1", 510)).is_none());
+        assert!(alt.lift_break(&brk("chr6", 510)).is_none());
+    }
+
+    #[test]
+    fn load_alt_file() {
+        let path = std::env::temp_dir().join(format!("breakinator_alt_{}.alt", std::process::id()));
+        fs::write(&path, "@SQ\tSN:chr6\tLN:10000\n\
+            chr6_rev_alt\t16\tchr6\t3001\t60\t900M10S\t*\t0\t0\t*\t*\n\
+            chr6_fwd_alt\t0\tchr6\t1001\t60\t100H500M2D498M\t*\t0\t0\t*\t*\n\
+            chrUn_alt\t4\t*\t0\t0\t*\t*\t0\t0\t*\t*\n\
+            chr7_alt\tchr7\t100\t200\n").unwrap();
+        let alt = AltContigs::load(path.to_str().unwrap()).unwrap();
+        fs::remove_file(&path).ok();
+
+        //the clip of a reverse strand record is at the end of its CIGAR
+        let p = &alt.contigs["chr6_rev_alt"];
+        assert_eq!((p.start, p.end, p.reverse, p.clip), (3000, 3900, true, 10));
+        let p = &alt.contigs["chr6_fwd_alt"];
+        assert_eq!((p.start, p.end, p.reverse, p.clip), (1000, 2000, false, 100));
+        let p = &alt.contigs["chr7_alt"];
+        assert_eq!((p.chr.as_str(), p.start, p.end, p.reverse, p.clip), ("chr7", 100, 200, false, 0));
+        assert!(!alt.contigs.contains_key("chrUn_alt"));
+    }
+}
@@ -0,0 +1,106 @@
+use std::{collections::{BTreeMap, HashMap, HashSet}, fs::File, io::{self, BufWriter, Write}};
+use crate::{Breakpoint, Cli, break_dist, is_fold_shape};
+
+//two (contig, start/end) contig ends joined by a read
+type LinkKey = ((String, &'static str), (String, &'static str));
+
+// split reads aggregated per contig of a draft assembly for --assembly-qc
+#[derive(Default)]
+pub struct AssemblyQc {
+    //internal split positions and read ids per contig
+    splits: HashMap<String, Vec<(i64, String)>>,
+    //reads joining two contig ends, keyed by (contig, start/end) pairs in sorted order
+    links: BTreeMap<LinkKey, Vec<(i64, String)>>,
+}
+
+impl AssemblyQc {
+    pub fn new() -> AssemblyQc {
+        AssemblyQc::default()
+    }
+
+    //record a breakpoint end as an internal split or a link between contig ends
+    pub fn add(&mut self, brk: &Breakpoint, args: &Cli) {
+        //junctions are kept on their geometry whatever their label: foldbacks are library artifacts
+        //and close same strand junctions within a contig are indels
+        if is_fold_shape(brk, args) {
+            return;
+        }
+        if brk.b1_chr == brk.b2_chr && (brk.directions == ">>" || brk.directions == "<<") && (break_dist(brk, args) as i64) < args.qc_min_dist {
+            return;
+        }
+        let end1 = contig_end(brk.b1_loc, brk.b1_chr_len, args.qc_end_dist);
+        let end2 = contig_end(brk.b2_loc, brk.b2_chr_len, args.qc_end_dist);
+        match (end1, end2) {
+            (Some(e1), Some(e2)) if brk.b1_chr != brk.b2_chr || e1 != e2 => {
+                let (a, b) = ((brk.b1_chr.clone(), e1), (brk.b2_chr.clone(), e2));
+                //positions are kept on the first contig of the link
+                let (key, loc) = if a <= b { ((a, b), brk.b1_loc) } else { ((b, a), brk.b2_loc) };
+                self.links.entry(key).or_default().push((loc, brk.read_id.clone()));
+            }
+            _ => {
+                if end1.is_none() {
+                    self.splits.entry(brk.b1_chr.clone()).or_default().push((brk.b1_loc, brk.read_id.clone()));
+                }
+                if end2.is_none() {
+                    self.splits.entry(brk.b2_chr.clone()).or_default().push((brk.b2_loc, brk.read_id.clone()));
+                }
+            }
+        }
+    }
+
+    //write candidate misjoins and scaffolding links supported by at least --qc-min-reads reads
+    pub fn write(&mut self, path: &str, args: &Cli) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "#Chr\tStart\tEnd\tType\tNum_reads\tJoin")?;
+
+        let mut contigs: Vec<&String> = self.splits.keys().collect();
+        contigs.sort();
+        for chr in contigs {
+            let mut splits = self.splits[chr].clone();
+            splits.sort();
+            //group split positions lying within --qc-window of the first split of the cluster
+            let mut i = 0;
+            while i < splits.len() {
+                let mut j = i;
+                while j < splits.len() && splits[j].0 - splits[i].0 <= args.qc_window {
+                    j += 1;
+                }
+                let reads = num_reads(&splits[i..j]);
+                if reads >= args.qc_min_reads {
+                    writeln!(writer, "{}\t{}\t{}\tMisjoin\t{}\t.", chr, splits[i].0, splits[j - 1].0 + 1, reads)?;
+                }
+                i = j;
+            }
+        }
+
+        for (((chr1, end1), (chr2, end2)), support) in &self.links {
+            let reads = num_reads(support);
+            if reads >= args.qc_min_reads {
+                let start = support.iter().map(|s| s.0).min().unwrap();
+                let end = support.iter().map(|s| s.0).max().unwrap() + 1;
+                writeln!(writer, "{}\t{}\t{}\tScaffoldLink\t{}\t{}:{},{}:{}", chr1, start, end, reads, chr1, end1, chr2, end2)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+//which end of the contig a position lies within end_dist of, the closer one for short contigs
+fn contig_end(loc: i64, len: i64, end_dist: i64) -> Option<&'static str> {
+    if len <= 0 {
+        return None;
+    }
+    if loc <= end_dist && loc <= len - loc {
+        Some("start")
+    } else if len - loc <= end_dist {
+        Some("end")
+    } else if loc <= end_dist {
+        Some("start")
+    } else {
+        None
+    }
+}
+
+fn num_reads(support: &[(i64, String)]) -> u64 {
+    support.iter().map(|s| &s.1).collect::<HashSet<_>>().len() as u64
+}
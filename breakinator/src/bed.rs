@@ -0,0 +1,83 @@
+use std::{collections::HashMap, io::{self, BufRead, BufReader}};
+use rust_htslib::bgzf;
+
+// intervals of one contig sorted by start, with the running max end to find overlapping intervals
+struct ContigRegions {
+    intervals: Vec<(i64, i64, String)>,
+    max_end: Vec<i64>,
+}
+
+// regions of a BED file (plain or bgzipped) indexed by contig
+pub struct Regions {
+    contigs: HashMap<String, ContigRegions>,
+}
+
+impl Regions {
+    pub fn load(path: &str) -> io::Result<Regions> {
+        let reader = bgzf::Reader::from_path(path).map_err(io::Error::other)?;
+        let mut by_contig: HashMap<String, Vec<(i64, i64, String)>> = HashMap::new();
+        for line in BufReader::new(reader).lines() {
+            let line = line?;
+            if line.starts_with('#') || line.starts_with("track") || line.starts_with("browser") || line.trim().is_empty() {
+                continue;
+            }
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() < 3 {
+                eprintln!("Warning: skipping malformed BED line: {}", line);
+                continue;
+            }
+            let start = fields[1].parse::<i64>().expect("invalid BED start");
+            let end = fields[2].parse::<i64>().expect("invalid BED end");
+            let name = fields.get(3).unwrap_or(&".").to_string();
+            by_contig.entry(fields[0].to_string()).or_default().push((start, end, name));
+        }
+        Ok(Regions::from_intervals(by_contig))
+    }
+
+    //index named 0-based half-open intervals grouped by contig
+    pub fn from_intervals(by_contig: HashMap<String, Vec<(i64, i64, String)>>) -> Regions {
+        let mut contigs = HashMap::new();
+        for (chr, mut intervals) in by_contig {
+            intervals.sort_by_key(|i| i.0);
+            let mut max_end = Vec::with_capacity(intervals.len());
+            let mut cur_max = i64::MIN;
+            for i in &intervals {
+                cur_max = cur_max.max(i.1);
+                max_end.push(cur_max);
+            }
+            contigs.insert(chr, ContigRegions{intervals, max_end});
+        }
+        Regions{contigs}
+    }
+
+    //all intervals containing the 0-based position
+    pub fn overlapping(&self, chr: &str, pos: i64) -> Vec<&(i64, i64, String)> {
+        let mut out = Vec::new();
+        if let Some(c) = self.contigs.get(chr) {
+            //walk back from the last interval starting at or before pos while an earlier one can still reach it
+            let mut i = c.intervals.partition_point(|iv| iv.0 <= pos);
+            while i > 0 && c.max_end[i - 1] > pos {
+                i -= 1;
+                if c.intervals[i].1 > pos {
+                    out.push(&c.intervals[i]);
+                }
+            }
+        }
+        out
+    }
+
+    pub fn contains(&self, chr: &str, pos: i64) -> bool {
+        !self.overlapping(chr, pos).is_empty()
+    }
+
+    //any interval overlapping the 0-based half-open range [start, end)
+    pub fn overlaps(&self, chr: &str, start: i64, end: i64) -> bool {
+        match self.contigs.get(chr) {
+            Some(c) => {
+                let i = c.intervals.partition_point(|iv| iv.0 < end);
+                i > 0 && c.max_end[i - 1] > start
+            }
+            None => false,
+        }
+    }
+}
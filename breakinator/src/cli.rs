@@ -1,13 +1,17 @@
-use clap::Parser;
+ use clap::{Args, Parser, Subcommand, ValueEnum};
 
 
 #[derive(Parser, Debug)]
-#[command( name = "breakinator", about = "Flag foldbacks and chimeric reads from SAM/BAM/CRAM or PAF input", version = "1.0")]
+#[command( name = "breakinator", about = "Flag foldbacks and chimeric reads from SAM/BAM/CRAM or PAF input", version = "1.0", subcommand_negates_reqs = true)]
 
 pub struct Cli {
+    // optional mode run instead of breakpoint detection
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     // 
     #[arg(short = 'i', long, value_name = "FILE", required = true, help="SAM/BAM/CRAM file sorted by read IDs")]
-    pub input: String,
+    pub input: Option<String>,
 
     // input is PAF file
     #[arg(long,value_name = "BOOL", default_value_t = false, help = "Input file is PAF")]
@@ -26,7 +30,7 @@ pub struct Cli {
     pub no_sym: bool,
 
     // reference genome used for cram compression
-    #[arg(short, long, value_name = "FASTA", help="Reference genome FASTA used (must be provided for CRAM input")]
+    #[arg(short, long, value_name = "FASTA", help="Reference genome FASTA used (must be provided for CRAM input and sequence based options)")]
     pub genome: Option<String>,
 
     // [0-1], With --no_sym, Proportion from center on either side to be considered foldback artifact
@@ -55,5 +59,241 @@ pub struct Cli {
 
     // number of threads to use
     #[arg(short, long,value_name = "INT", default_value_t = 2, help = "Number of threads to use for BAM/CRAM I/O")]
-    pub threads: usize
-}
\ No newline at end of file
+    pub threads: usize,
+
+    // panel of normals of recurrent artifact junctions
+    #[arg(long, value_name = "FILE", help = "Panel of normals; artifact breakpoints matching a recurrent panel site are labelled SystematicArtifact")]
+    pub panel: Option<String>,
+
+    // add the artifact junctions of this run to a panel of normals
+    #[arg(long, value_name = "FILE", help = "Add the artifact junctions of this sample to the panel of normals FILE (created if missing)")]
+    pub panel_add: Option<String>,
+
+    // Minimum number of samples supporting a panel site
+    #[arg(long, value_name = "INT", default_value_t = 2, help = "Minimum number of panel samples for a site to be considered systematic")]
+    pub panel_min_samples: u32,
+
+    // Max distance between breakpoint ends and a panel site
+    #[arg(long, value_name = "INT", default_value_t = 100, help = "Max distance (bps) of both breakpoint ends from a panel site to match it")]
+    pub panel_window: i64,
+
+    // known rearrangements of the sample
+    #[arg(long, value_name = "FILE", help = "BEDPE or VCF of known SV junctions; matching breakpoints are labelled KnownSV")]
+    pub known_sv: Option<String>,
+
+    // Max distance between breakpoint ends and a known junction
+    #[arg(long, value_name = "INT", default_value_t = 1000, help = "Max distance (bps) of both breakpoint ends from a known SV junction to match it")]
+    pub known_sv_window: i64,
+
+    // regions excluded from the summary statistics
+    #[arg(long, value_name = "BED", help = "BED of excluded regions (e.g. centromeres, satellites); breakpoints with either end inside are labelled Masked")]
+    pub mask: Option<String>,
+
+    // annotation tracks reported for each breakpoint end
+    #[arg(long, value_name = "NAME=BED", help = "Named BED annotation track (e.g. segdup=segdups.bed); can be given multiple times")]
+    pub annot: Vec<String>,
+
+    // Report reference sequence context of each junction
+    #[arg(long, value_name = "BOOL", default_value_t = false, help = "Print microhomology length and flank GC content of each breakpoint (requires --genome)")]
+    pub seq_context: bool,
+
+    // Size of the reference flanks around each breakpoint end
+    #[arg(long, value_name = "INT", default_value_t = 20, help = "Size (bps) of the reference flanks on either side of each breakpoint end used by --seq-context")]
+    pub flank: i64,
+
+    // Scan the reference around foldbacks for inverted repeats
+    #[arg(long, value_name = "BOOL", default_value_t = false, help = "Scan the reference around foldback breakpoints for inverted repeats and label foldbacks at reference palindromes InvertedRepeat (requires --genome)")]
+    pub inverted_repeats: bool,
+
+    // Max distance of the inverted repeat centre from the fold
+    #[arg(long, value_name = "INT", default_value_t = 100, help = "Max distance (bps) of the inverted repeat centre from the foldback breakpoint")]
+    pub ir_window: i64,
+
+    // Max spacer between inverted repeat arms
+    #[arg(long, value_name = "INT", default_value_t = 50, help = "Max spacer length (bps) between the two inverted repeat arms")]
+    pub max_ir_spacer: i64,
+
+    // Minimum arm length to be considered a reference palindrome
+    #[arg(long, value_name = "INT", default_value_t = 15, help = "Minimum inverted repeat arm length (bps) to label a foldback InvertedRepeat")]
+    pub min_ir_arm: u32,
+
+    // Label breakpoints flanking reference assembly gaps
+    #[arg(long, value_name = "BOOL", default_value_t = false, help = "Label breakpoints whose two ends flank the same N-gap of the reference GapSpanning (requires --genome)")]
+    pub gaps: bool,
+
+    // Minimum run of N to be considered an assembly gap
+    #[arg(long, value_name = "INT", default_value_t = 10, help = "Minimum length (bps) of an N run to be considered an assembly gap")]
+    pub min_gap: i64,
+
+    // Max distance of breakpoint ends from the gap edges
+    #[arg(long, value_name = "INT", default_value_t = 1000, help = "Max distance (bps) of each breakpoint end from the gap edge it flanks")]
+    pub gap_tol: i64,
+
+    // circular contigs where distances are taken around the origin
+    #[arg(long, value_name = "CONTIGS", value_delimiter = ',', default_value = "chrM,MT", help = "Comma separated circular contigs; breakpoint distances on them are taken around the origin")]
+    pub circular: Vec<String>,
+
+    // ALT/HLA/decoy contigs and their primary regions
+    #[arg(long, value_name = "FILE", help = "bwa .alt file or TSV (alt_contig, primary_chr, start, end); junctions between an ALT contig and its primary region are not called chimeric")]
+    pub alt: Option<String>,
+
+    // genome or taxon of each contig of a combined reference
+    #[arg(long, value_name = "FILE", help = "TSV of contig to group (e.g. genome or taxon); chimeras are broken down into intra- and inter-group junctions")]
+    pub groups: Option<String>,
+
+    // Max distance from a contig end for artifacts to be labelled ContigEnd
+    #[arg(long, value_name = "INT", help = "Label artifact breakpoints with either end within INT bps of a contig end ContigEnd")]
+    pub end_dist: Option<i64>,
+
+    // telomeric repeat motif scanned in the reference
+    #[arg(long, value_name = "SEQ", help = "Label artifact breakpoints with either end in reference repeats of this telomeric motif (e.g. TTAGGG) ContigEnd (requires --genome)")]
+    pub telomere_motif: Option<String>,
+
+    // output of candidate misjoins and scaffolding links when reads are aligned to a draft assembly
+    #[arg(long, value_name = "FILE", help = "Write candidate assembly misjoins and scaffolding links between contig ends to this TSV")]
+    pub assembly_qc: Option<String>,
+
+    // Max distance between split positions grouped into one misjoin
+    #[arg(long, value_name = "INT", default_value_t = 100, help = "Max distance between split positions grouped into one candidate misjoin")]
+    pub qc_window: i64,
+
+    // Min reads supporting a misjoin or link
+    #[arg(long, value_name = "INT", default_value_t = 3, help = "Min reads supporting a candidate misjoin or scaffolding link")]
+    pub qc_min_reads: u64,
+
+    // Max distance from a contig end for a split to count as a contig end
+    #[arg(long, value_name = "INT", default_value_t = 1000, help = "Splits within INT bps of a contig end are scaffolding links rather than misjoins")]
+    pub qc_end_dist: i64,
+
+    // Min distance between the ends of a same strand junction within a contig
+    #[arg(long, value_name = "INT", default_value_t = 1000, help = "Min distance (bps) between the ends of a same strand junction within a contig for it to count as misjoin evidence")]
+    pub qc_min_dist: i64,
+
+    // gene annotation for cDNA and direct RNA runs
+    #[arg(long, value_name = "FILE", help = "GTF/GFF3 gene annotation; breakpoint ends are annotated with gene, strand and exon/intron and chimeras joining two genes are summarized")]
+    pub gtf: Option<String>,
+
+    // Max distance of a split from an exon boundary
+    #[arg(long, value_name = "INT", default_value_t = 5, help = "Max distance of a breakpoint end from an exon boundary; same strand splits from an exon end to a downstream exon start of one gene are labelled Spliced")]
+    pub splice_tol: i64,
+
+    // target regions of adaptive sampling or Cas9 enrichment
+    #[arg(long, value_name = "BED", help = "BED of target regions; artifact rates are reported separately for reads with a segment overlapping a target and off-target reads")]
+    pub targets: Option<String>,
+
+    // aux tag, or @RG header field as RG:FIELD, used to split the counts
+    #[arg(long, value_name = "TAG", help = "Report counts for each value of this aux tag of the primary alignment (e.g. RG, BC, ch, CB), or of a field of its @RG header line given as RG:FIELD (e.g. RG:SM); group rows of --tabular only have the read and breakpoint class counts")]
+    pub group_by: Option<String>,
+
+    // output of artifact rates over the run
+    #[arg(long, value_name = "FILE", help = "Write reads and artifact rates per time bin of the read start time (st:Z tag) to this TSV")]
+    pub time_bins: Option<String>,
+
+    // width of the time bins
+    #[arg(long, value_name = "INT", default_value_t = 60, help = "Minutes per bin of --time-bins")]
+    pub bin_minutes: i64,
+
+    // ONT sequencing summary joined to the reads by read_id
+    #[arg(long, value_name = "FILE", help = "sequencing_summary.txt of the run; channel, mux, start_time, duration, end_reason and mean qscore are added to the output and artifact rates are reported per end_reason")]
+    pub seq_summary: Option<String>,
+
+    // link reads split by the basecaller through their parent read ID
+    #[arg(long, help = "Link reads split by Dorado through their parent read ID (pi:Z tag) and report the splits and whether children map adjacently")]
+    pub split_parents: bool,
+
+    // Max distance between consecutive children for them to map adjacently
+    #[arg(long, value_name = "INT", default_value_t = 1000, help = "Max reference distance between consecutive children of a split read for them to map adjacently")]
+    pub parent_window: i64,
+
+    // adapter and barcode sequences searched for between segments of SAM/BAM/CRAM reads
+    #[arg(long, value_name = "SEQS", value_delimiter = ',', help = "Comma separated adapter/barcode sequences (NAME=SEQ or SEQ) searched for in the unaligned read sequence between segments (SAM/BAM/CRAM only)")]
+    pub adapters: Vec<String>,
+
+    // Max substitutions in an adapter match
+    #[arg(long, value_name = "INT", default_value_t = 2, help = "Max mismatches in an --adapters match")]
+    pub adapter_mismatches: u32,
+
+    // label read-through hairpins separately from other foldbacks
+    #[arg(long, help = "Label foldbacks whose arms cover nearly the same reference interval across almost the whole read Palindromic")]
+    pub palindromic: bool,
+
+    // Min arm overlap and read coverage of the arms
+    #[arg(long, value_name = "FLOAT", default_value_t = 0.9, help = "Min reference overlap of the arms (fraction of the longer arm) and fraction of the read covered by the arms for --palindromic")]
+    pub palindrome_overlap: f32,
+
+    // geometry of the two arms of foldbacks
+    #[arg(long, help = "Add the read space lengths, reference intervals, reference overlap fractions and length ratio of the arms of foldback breakpoints to the output")]
+    pub arm_geometry: bool,
+
+    // continuous scores for filtering downstream
+    #[arg(long, help = "Add a symmetry score (distance of the fold from the read midpoint / read length) for foldback shaped junctions and a confidence in [0-1] for each breakpoint label to the output")]
+    pub scores: bool,
+
+    // classify reads by the pattern of all their segments
+    #[arg(long, help = "Count reads by the pattern of their segments: concatemers, multi-way chimeras, mixed foldback and chimeric reads and complex reads")]
+    pub read_patterns: bool,
+
+    // Max segments of a read before it is complex
+    #[arg(long, value_name = "INT", default_value_t = 5, help = "Reads with more than this many segments are counted as complex by --read-patterns")]
+    pub max_segments: usize,
+
+    // how the breakpoint labels of a read decide its read level class
+    #[arg(long, value_enum, default_value_t = ReadPolicy::Majority, help = "Read level classification from the breakpoints of a read")]
+    pub read_policy: ReadPolicy,
+
+    // Min fraction of artifact breakpoints for --read-policy min-fraction
+    #[arg(long, value_name = "FLOAT", default_value_t = 0.5, help = "[0-1], Min fraction of breakpoints of a read that are Foldback, Chimeric or Palindromic for it to be an artifact with --read-policy min-fraction")]
+    pub min_artifact_frac: f32
+}
+
+// read level aggregation of breakpoint labels
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum ReadPolicy {
+    // any artifact breakpoint makes the read an artifact, the more frequent class wins and ties go to Foldback
+    Majority,
+    // class of the first breakpoint in read order
+    FirstBreakpoint,
+    // any chimeric breakpoint makes the read chimeric
+    AnyChimera,
+    // majority among artifact breakpoints once they make up --min-artifact-frac of the breakpoints
+    MinFraction,
+    // reads with both foldback and chimeric breakpoints are Mixed, otherwise majority
+    Mixed,
+}
+
+impl Cli {
+    //input file, only absent when a subcommand is run instead
+    pub fn input(&self) -> &str {
+        self.input.as_deref().expect("--input is required")
+    }
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    // annotate SV calls with the artifact classification of their supporting reads
+    #[command(name = "annotate-vcf", about = "Annotate an SV caller VCF with the number of supporting reads flagged as artifacts")]
+    AnnotateVcf(AnnotateVcfArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct AnnotateVcfArgs {
+    // SV calls listing supporting reads in INFO
+    #[arg(short = 'v', long, value_name = "VCF", required = true, help = "SV VCF (e.g. Sniffles/cuteSV) with supporting read names in INFO")]
+    pub vcf: String,
+
+    // breakpoint output of a breakinator run on the same reads
+    #[arg(short = 'b', long, value_name = "FILE", required = true, help = "Breakinator breakpoint output file")]
+    pub breaks: String,
+
+    // Output file name
+    #[arg(short = 'o', long, value_name = "FILE", default_value = "breakinator_annotated.vcf", help = "Output VCF file name")]
+    pub out: String,
+
+    // INFO field holding the supporting read names
+    #[arg(long, value_name = "TAG", default_value = "RNAMES", help = "INFO field listing the supporting read names")]
+    pub rnames_tag: String,
+
+    // [0-1], fraction of artifact supporting reads to FILTER a call
+    #[arg(long, value_name = "FLOAT", help = "[0-1], Set FILTER to BKN_ARTIFACT when at least this fraction of supporting reads are artifact reads")]
+    pub filter_frac: Option<f32>,
+}
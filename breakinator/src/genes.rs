@@ -0,0 +1,210 @@
+use std::{collections::HashMap, io::{self, BufRead, BufReader}};
+use rust_htslib::bgzf;
+use crate::Breakpoint;
+use crate::bed::Regions;
+
+// genes and exons of a GTF/GFF3 annotation given with --gtf, both labelled by gene ID
+pub struct GeneModel {
+    genes: Regions,
+    exons: Regions,
+    //display name and strand of each gene by contig and gene ID
+    names: HashMap<(String, String), (String, char)>,
+}
+
+impl GeneModel {
+    //read a GTF or GFF3 file (plain or bgzipped), genes are keyed by contig and gene_id or ID and shown by gene_name or Name
+    pub fn load(path: &str) -> io::Result<GeneModel> {
+        let reader = bgzf::Reader::from_path(path).map_err(io::Error::other)?;
+        //gene spans by contig and gene ID as (start, end, strand), extended by their exons
+        let mut spans: HashMap<(String, String), (i64, i64, char)> = HashMap::new();
+        //gene names by gene ID, genes of different loci can share a name
+        let mut gene_names: HashMap<String, String> = HashMap::new();
+        //exons with the gene ID (GTF) or parent ID (GFF3) they belong to
+        let mut exon_list: Vec<(String, i64, i64, char, String)> = Vec::new();
+        //GFF3 IDs of transcripts, resolved to gene IDs once the file is read
+        let mut parent_ids: HashMap<String, String> = HashMap::new();
+        for line in BufReader::new(reader).lines() {
+            let line = line?;
+            if line.starts_with('#') || line.trim().is_empty() {
+                continue;
+            }
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() < 9 {
+                eprintln!("Warning: skipping malformed GTF/GFF line: {}", line);
+                continue;
+            }
+            //GTF/GFF coordinates are 1-based and inclusive
+            let start = fields[3].parse::<i64>().expect("invalid GTF/GFF start") - 1;
+            let end = fields[4].parse::<i64>().expect("invalid GTF/GFF end");
+            let strand = fields[6].chars().next().unwrap_or('.');
+            let attrs = parse_attributes(fields[8]);
+            match fields[2] {
+                "gene" => {
+                    let id = match ["gene_id", "ID", "gene_name", "Name"].iter().find_map(|k| attrs.get(*k)) {
+                        Some(id) => id.clone(),
+                        None => continue,
+                    };
+                    if let Some(name) = ["gene_name", "Name"].iter().find_map(|k| attrs.get(*k)) {
+                        gene_names.insert(id.clone(), name.clone());
+                    }
+                    extend_span(&mut spans, fields[0], &id, start, end, strand);
+                }
+                "transcript" | "mRNA" => {
+                    if let (Some(id), Some(parent)) = (attrs.get("ID"), attrs.get("Parent")) {
+                        parent_ids.insert(id.clone(), parent.split(',').next().unwrap().to_string());
+                    }
+                }
+                "exon" => {
+                    let key = match ["gene_id", "Parent", "gene_name"].iter().find_map(|k| attrs.get(*k)) {
+                        Some(key) => key.split(',').next().unwrap().to_string(),
+                        None => continue,
+                    };
+                    //GTF annotations without gene records name the gene on each exon
+                    if let Some(name) = attrs.get("gene_name") {
+                        gene_names.entry(key.clone()).or_insert_with(|| name.clone());
+                    }
+                    exon_list.push((fields[0].to_string(), start, end, strand, key));
+                }
+                _ => {}
+            }
+        }
+
+        let mut exons: HashMap<String, Vec<(i64, i64, String)>> = HashMap::new();
+        for (chr, start, end, strand, key) in exon_list {
+            //GFF3 exons point to a transcript which points to a gene
+            let id = parent_ids.get(&key).unwrap_or(&key).clone();
+            extend_span(&mut spans, &chr, &id, start, end, strand);
+            exons.entry(chr).or_default().push((start, end, id));
+        }
+        let mut genes: HashMap<String, Vec<(i64, i64, String)>> = HashMap::new();
+        let mut names = HashMap::new();
+        for ((chr, id), (start, end, strand)) in spans {
+            genes.entry(chr.clone()).or_default().push((start, end, id.clone()));
+            let name = gene_names.get(&id).unwrap_or(&id).clone();
+            names.insert((chr, id), (name, strand));
+        }
+        Ok(GeneModel{genes: Regions::from_intervals(genes), exons: Regions::from_intervals(exons), names})
+    }
+
+    //genes with their strand and whether the position is at an exon boundary, in an exon or in an intron
+    pub fn annotate(&self, chr: &str, pos: i64, tol: i64) -> (Vec<(String, char)>, Option<String>) {
+        let genes: Vec<(String, char)> = self.genes.overlapping(chr, pos).iter()
+            .map(|g| self.names.get(&(chr.to_string(), g.2.clone())).cloned().unwrap_or((g.2.clone(), '.'))).collect();
+        if genes.is_empty() {
+            return (genes, None);
+        }
+        let feature = if self.exon_ends_near(chr, pos, tol).is_some() || self.exon_starts_near(chr, pos, tol).is_some() {
+            "exon_boundary"
+        } else if self.exons.contains(chr, pos) {
+            "exon"
+        } else {
+            "intron"
+        };
+        (genes, Some(feature.to_string()))
+    }
+
+    //same strand split of one gene from the end of an exon to the start of a downstream exon in read order
+    pub fn is_splice_junction(&self, brk: &Breakpoint, tol: i64) -> bool {
+        if brk.b1_chr != brk.b2_chr {
+            return false;
+        }
+        let forward = brk.directions == ">>" && brk.b1_loc < brk.b2_loc;
+        let reverse = brk.directions == "<<" && brk.b1_loc > brk.b2_loc;
+        if !(forward || reverse) {
+            return false;
+        }
+        let (lo, hi) = (brk.b1_loc.min(brk.b2_loc), brk.b1_loc.max(brk.b2_loc));
+        let (donors, acceptors) = match (self.exon_ends_near(&brk.b1_chr, lo, tol), self.exon_starts_near(&brk.b1_chr, hi, tol)) {
+            (Some(d), Some(a)) => (d, a),
+            _ => return false,
+        };
+        donors.iter().any(|g| acceptors.contains(g))
+    }
+
+    //IDs of genes with an exon ending within tol of pos, exons longer than 2 * tol contain pos - tol - 1
+    fn exon_ends_near(&self, chr: &str, pos: i64, tol: i64) -> Option<Vec<&String>> {
+        let genes: Vec<&String> = self.exons.overlapping(chr, pos - tol - 1).into_iter()
+            .filter(|e| (e.1 - pos).abs() <= tol).map(|e| &e.2).collect();
+        if genes.is_empty() { None } else { Some(genes) }
+    }
+
+    //IDs of genes with an exon starting within tol of pos, exons longer than 2 * tol contain pos + tol
+    fn exon_starts_near(&self, chr: &str, pos: i64, tol: i64) -> Option<Vec<&String>> {
+        let genes: Vec<&String> = self.exons.overlapping(chr, pos + tol).into_iter()
+            .filter(|e| (e.0 - pos).abs() <= tol).map(|e| &e.2).collect();
+        if genes.is_empty() { None } else { Some(genes) }
+    }
+}
+
+fn extend_span(spans: &mut HashMap<(String, String), (i64, i64, char)>, chr: &str, id: &str, start: i64, end: i64, strand: char) {
+    let span = spans.entry((chr.to_string(), id.to_string())).or_insert((start, end, strand));
+    span.0 = span.0.min(start);
+    span.1 = span.1.max(end);
+}
+
+//attributes column of GTF (key "value";) or GFF3 (key=value;) records
+fn parse_attributes(column: &str) -> HashMap<String, String> {
+    let mut attrs = HashMap::new();
+    for attr in column.split(';') {
+        let attr = attr.trim();
+        let pair = if attr.contains('=') { attr.split_once('=') } else { attr.split_once(' ') };
+        if let Some((key, value)) = pair {
+            //GTF can repeat keys such as tag, the first value is kept
+            attrs.entry(key.trim().to_string()).or_insert_with(|| value.trim().trim_matches('"').to_string());
+        }
+    }
+    attrs
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use super::GeneModel;
+    use crate::Breakpoint;
+
+    //two loci of the same gene name on different contigs and a two exon gene on the reverse strand
+    fn model() -> GeneModel {
+        let path = std::env::temp_dir().join(format!("breakinator_genes_{}.gtf", std::process::id()));
+        fs::write(&path, concat!(
+            "chr1\ttest\tgene\t1001\t2000\t.\t+\t.\tgene_id \"G1\"; gene_name \"U6\";\n",
+            "chr1\ttest\texon\t1001\t2000\t.\t+\t.\tgene_id \"G1\"; gene_name \"U6\";\n",
+            "chr2\ttest\tgene\t90001\t91000\t.\t+\t.\tgene_id \"G2\"; gene_name \"U6\";\n",
+            "chr2\ttest\texon\t90001\t91000\t.\t+\t.\tgene_id \"G2\"; gene_name \"U6\";\n",
+            "chr1\ttest\tgene\t20001\t30000\t.\t-\t.\tgene_id \"G3\"; gene_name \"ABC\";\n",
+            "chr1\ttest\texon\t20001\t21000\t.\t-\t.\tgene_id \"G3\"; gene_name \"ABC\";\n",
+            "chr1\ttest\texon\t29001\t30000\t.\t-\t.\tgene_id \"G3\"; gene_name \"ABC\";\n",
+        )).unwrap();
+        let genes = GeneModel::load(path.to_str().unwrap()).unwrap();
+        fs::remove_file(&path).ok();
+        genes
+    }
+
+    fn brk(loc1: i64, directions: &str, chr2: &str, loc2: i64) -> Breakpoint {
+        Breakpoint{b1_chr: String::from("chr1"), b1_loc: loc1, directions: directions.to_string(), b2_chr: chr2.to_string(), b2_loc: loc2, ..Default::default()}
+    }
+
+    #[test]
+    fn annotate() {
+        let genes = model();
+        assert_eq!(genes.annotate("chr1", 1500, 10), (vec![(String::from("U6"), '+')], Some(String::from("exon"))));
+        assert_eq!(genes.annotate("chr2", 90500, 10), (vec![(String::from("U6"), '+')], Some(String::from("exon"))));
+        //loci sharing a name are not merged into one span across contigs or between them
+        assert_eq!(genes.annotate("chr1", 15000, 10), (vec![], None));
+        assert_eq!(genes.annotate("chr2", 1500, 10), (vec![], None));
+        assert_eq!(genes.annotate("chr1", 25000, 10), (vec![(String::from("ABC"), '-')], Some(String::from("intron"))));
+        assert_eq!(genes.annotate("chr1", 21005, 10), (vec![(String::from("ABC"), '-')], Some(String::from("exon_boundary"))));
+    }
+
+    #[test]
+    fn splice_junctions() {
+        let genes = model();
+        assert!(genes.is_splice_junction(&brk(21000, ">>", "chr1", 29000), 10));
+        assert!(genes.is_splice_junction(&brk(29003, "<<", "chr1", 20995), 10));
+        //not at exon ends, wrong orientation or exons of different genes
+        assert!(!genes.is_splice_junction(&brk(20500, ">>", "chr1", 29000), 10));
+        assert!(!genes.is_splice_junction(&brk(21000, "><", "chr1", 29000), 10));
+        assert!(!genes.is_splice_junction(&brk(29000, ">>", "chr1", 21000), 10));
+        assert!(!genes.is_splice_junction(&brk(2000, ">>", "chr1", 29000), 10));
+        assert!(!genes.is_splice_junction(&brk(2000, ">>", "chr2", 90000), 10));
+    }
+}
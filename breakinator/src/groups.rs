@@ -0,0 +1,31 @@
+use std::{collections::HashMap, fs::File, io::{self, BufRead, BufReader}};
+
+// genome or taxon each contig of a combined reference belongs to
+pub struct ContigGroups {
+    groups: HashMap<String, String>,
+}
+
+impl ContigGroups {
+    //read a TSV of contig, group
+    pub fn load(path: &str) -> io::Result<ContigGroups> {
+        let mut groups = HashMap::new();
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            if line.starts_with('#') || line.trim().is_empty() {
+                continue;
+            }
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() < 2 {
+                eprintln!("Warning: skipping malformed contig group line: {}", line);
+                continue;
+            }
+            groups.insert(fields[0].to_string(), fields[1].to_string());
+        }
+        Ok(ContigGroups{groups})
+    }
+
+    //contigs missing from the mapping are reported as ungrouped
+    pub fn group_of(&self, chr: &str) -> &str {
+        self.groups.get(chr).map_or("ungrouped", |g| g.as_str())
+    }
+}
@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+use crate::Breakpoint;
+
+// a breakpoint junction stored in canonical orientation, so the same junction seen on reads
+// from either strand gives the same ends and directions
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Junction {
+    pub chr1: String,
+    pub pos1: i64,
+    pub directions: String,
+    pub chr2: String,
+    pub pos2: i64,
+}
+
+impl Junction {
+    pub fn new(chr1: &str, pos1: i64, directions: &str, chr2: &str, pos2: i64) -> Junction {
+        //reading the junction from the other strand swaps the ends and flips both directions
+        if (chr1, pos1) > (chr2, pos2) {
+            let flipped: String = directions.chars().rev().map(|d| match d {
+                '>' => '<',
+                '<' => '>',
+                _ => d,
+            }).collect();
+            return Junction{chr1: chr2.to_string(), pos1: pos2, directions: flipped, chr2: chr1.to_string(), pos2: pos1};
+        }
+        Junction{chr1: chr1.to_string(), pos1, directions: directions.to_string(), chr2: chr2.to_string(), pos2}
+    }
+
+    pub fn from_break(brk: &Breakpoint) -> Junction {
+        Junction::new(&brk.b1_chr, brk.b1_loc, &brk.directions, &brk.b2_chr, brk.b2_loc)
+    }
+}
+
+//contigs and directions of a junction with the bin of its first end
+type BinKey = (String, String, String, i64);
+
+// junctions binned by their first end so that lookups within a window only check neighbouring bins
+pub struct JunctionIndex<T> {
+    window: i64,
+    bin_size: i64,
+    bins: HashMap<BinKey, Vec<(Junction, T)>>,
+}
+
+impl<T> JunctionIndex<T> {
+    pub fn new(window: i64) -> JunctionIndex<T> {
+        JunctionIndex{window, bin_size: window.max(1), bins: HashMap::new()}
+    }
+
+    fn key(&self, j: &Junction, bin: i64) -> BinKey {
+        (j.chr1.clone(), j.directions.clone(), j.chr2.clone(), bin)
+    }
+
+    pub fn insert(&mut self, j: Junction, value: T) {
+        let key = self.key(&j, j.pos1.div_euclid(self.bin_size));
+        self.bins.entry(key).or_default().push((j, value));
+    }
+
+    //find the closest stored junction with both ends within the window of the query
+    fn locate(&self, j: &Junction) -> Option<(BinKey, usize)> {
+        let bin = j.pos1.div_euclid(self.bin_size);
+        let mut best: Option<(BinKey, usize, i64)> = None;
+        for b in (bin - 1)..=(bin + 1) {
+            let key = self.key(j, b);
+            if let Some(sites) = self.bins.get(&key) {
+                for (i, (site, _)) in sites.iter().enumerate() {
+                    let d1 = (site.pos1 - j.pos1).abs();
+                    let d2 = (site.pos2 - j.pos2).abs();
+                    if d1 <= self.window && d2 <= self.window && best.as_ref().is_none_or(|x| d1 + d2 < x.2) {
+                        best = Some((key.clone(), i, d1 + d2));
+                    }
+                }
+            }
+        }
+        best.map(|(key, i, _)| (key, i))
+    }
+
+    pub fn get(&self, j: &Junction) -> Option<&T> {
+        let (key, i) = self.locate(j)?;
+        self.bins.get(&key).map(|sites| &sites[i].1)
+    }
+
+    pub fn get_mut(&mut self, j: &Junction) -> Option<&mut T> {
+        let (key, i) = self.locate(j)?;
+        self.bins.get_mut(&key).map(|sites| &mut sites[i].1)
+    }
+
+    //closest stored junction together with its value
+    pub fn get_entry_mut(&mut self, j: &Junction) -> Option<&mut (Junction, T)> {
+        let (key, i) = self.locate(j)?;
+        self.bins.get_mut(&key).map(|sites| &mut sites[i])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(Junction, T)> {
+        self.bins.values().flatten()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bins.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Junction, JunctionIndex};
+
+    #[test]
+    fn canonical_orientation() {
+        //the same junction read from the other strand swaps the ends and flips both directions
+        assert_eq!(Junction::new("chr2", 500, ">>", "chr1", 100), Junction::new("chr1", 100, "<<", "chr2", 500));
+        assert_eq!(Junction::new("chr1", 900, "<>", "chr1", 100), Junction::new("chr1", 100, "<>", "chr1", 900));
+        let j = Junction::new("chr1", 900, ">>", "chr1", 100);
+        assert_eq!((j.pos1, j.directions.as_str(), j.pos2), (100, "<<", 900));
+        //ends already in order are kept as given
+        let j = Junction::new("chr1", 100, "><", "chr1", 900);
+        assert_eq!((j.pos1, j.directions.as_str(), j.pos2), (100, "><", 900));
+    }
+
+    #[test]
+    fn window_lookup() {
+        let mut index = JunctionIndex::new(100);
+        index.insert(Junction::new("chr1", 1000, ">>", "chr1", 5000), 1);
+        index.insert(Junction::new("chr1", 1150, ">>", "chr1", 5000), 2);
+        assert_eq!(index.get(&Junction::new("chr1", 1060, ">>", "chr1", 4950)), Some(&1));
+        //the closest site wins when several are within the window
+        assert_eq!(index.get(&Junction::new("chr1", 1100, ">>", "chr1", 5000)), Some(&2));
+        //both ends must be within the window
+        assert_eq!(index.get(&Junction::new("chr1", 1000, ">>", "chr1", 5101)), None);
+        assert_eq!(index.get(&Junction::new("chr1", 1300, ">>", "chr1", 5000)), None);
+        //directions and contigs must match
+        assert_eq!(index.get(&Junction::new("chr1", 1000, "><", "chr1", 5000)), None);
+        assert_eq!(index.get(&Junction::new("chr1", 1000, ">>", "chr2", 5000)), None);
+        //a lookup from the other strand finds the same site
+        assert_eq!(index.get(&Junction::new("chr1", 5000, "<<", "chr1", 1000)), Some(&1));
+    }
+
+    #[test]
+    fn lookup_across_bins() {
+        let mut index = JunctionIndex::new(100);
+        index.insert(Junction::new("chr1", 199, "<>", "chr1", 199), ());
+        assert!(index.get(&Junction::new("chr1", 250, "<>", "chr1", 250)).is_some());
+        assert!(index.get(&Junction::new("chr1", 120, "<>", "chr1", 120)).is_some());
+        assert!(index.get(&Junction::new("chr1", 300, "<>", "chr1", 300)).is_none());
+        *index.get_mut(&Junction::new("chr1", 210, "<>", "chr1", 210)).unwrap() = ();
+        assert!(!index.is_empty());
+    }
+}
@@ -0,0 +1,84 @@
+use std::{fs::File, io::{self, BufRead, BufReader, Read}};
+use rust_htslib::bgzf;
+use crate::junction::{Junction, JunctionIndex};
+use crate::Breakpoint;
+
+// truth set of known rearrangement junctions, matched on their ends regardless of direction
+pub struct KnownSvs {
+    junctions: JunctionIndex<()>,
+}
+
+impl KnownSvs {
+    //read known junctions from a VCF (.vcf/.vcf.gz) or BEDPE file
+    pub fn load(path: &str, window: i64) -> io::Result<KnownSvs> {
+        let lower = path.to_lowercase();
+        let mut junctions = JunctionIndex::new(window);
+        if lower.ends_with(".vcf") || lower.ends_with(".vcf.gz") {
+            let reader = bgzf::Reader::from_path(path).map_err(io::Error::other)?;
+            load_vcf(BufReader::new(reader), &mut junctions)?;
+        } else {
+            load_bedpe(BufReader::new(File::open(path)?), &mut junctions)?;
+        }
+        Ok(KnownSvs{junctions})
+    }
+
+    pub fn contains(&self, brk: &Breakpoint) -> bool {
+        let j = Junction::new(&brk.b1_chr, brk.b1_loc, "", &brk.b2_chr, brk.b2_loc);
+        self.junctions.get(&j).is_some()
+    }
+}
+
+//BEDPE: chrom1 start1 end1 chrom2 start2 end2, the middle of each interval is used as the junction end
+fn load_bedpe<R: Read>(reader: BufReader<R>, junctions: &mut JunctionIndex<()>) -> io::Result<()> {
+    for line in reader.lines() {
+        let line = line?;
+        if line.starts_with('#') || line.starts_with("track") || line.trim().is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() < 6 {
+            eprintln!("Warning: skipping malformed BEDPE line: {}", line);
+            continue;
+        }
+        let mid1 = (fields[1].parse::<i64>().expect("invalid BEDPE start1") + fields[2].parse::<i64>().expect("invalid BEDPE end1")) / 2;
+        let mid2 = (fields[4].parse::<i64>().expect("invalid BEDPE start2") + fields[5].parse::<i64>().expect("invalid BEDPE end2")) / 2;
+        junctions.insert(Junction::new(fields[0], mid1, "", fields[3], mid2), ());
+    }
+    Ok(())
+}
+
+//VCF: breakend ALTs (N[chr:pos[), symbolic SVs with END and translocations with CHR2/END in INFO
+fn load_vcf<R: Read>(reader: BufReader<R>, junctions: &mut JunctionIndex<()>) -> io::Result<()> {
+    for line in reader.lines() {
+        let line = line?;
+        if line.starts_with('#') || line.trim().is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() < 8 {
+            continue;
+        }
+        //VCF positions are 1-based
+        let chr = fields[0];
+        let pos = fields[1].parse::<i64>().expect("invalid VCF position") - 1;
+        let alt = fields[4];
+
+        if let Some(start) = alt.find(['[', ']']) {
+            let mate = &alt[start + 1..];
+            let mate = &mate[..mate.find(['[', ']']).unwrap_or(mate.len())];
+            if let Some((mate_chr, mate_pos)) = mate.rsplit_once(':') {
+                let mate_pos = mate_pos.parse::<i64>().expect("invalid breakend position") - 1;
+                junctions.insert(Junction::new(chr, pos, "", mate_chr, mate_pos), ());
+            }
+            continue;
+        }
+
+        let info = |key: &str| fields[7].split(';').find_map(|f| f.strip_prefix(key).and_then(|v| v.strip_prefix('=')));
+        if let Some(end) = info("END") {
+            let end = end.parse::<i64>().expect("invalid INFO/END") - 1;
+            let chr2 = info("CHR2").unwrap_or(chr);
+            junctions.insert(Junction::new(chr, pos, "", chr2, end), ());
+        }
+    }
+    Ok(())
+}
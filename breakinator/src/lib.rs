@@ -1,11 +1,103 @@
 pub mod cli;
 pub use cli::Cli;
+use cli::ReadPolicy;
+use clap::ValueEnum;
 pub mod paf;
 pub mod sam; 
-use std::{ env, io::{self, Write}};
+pub mod junction;
+pub mod panel;
+pub mod vcf;
+pub mod known_sv;
+pub mod bed;
+pub mod refseq;
+pub mod alt;
+pub mod groups;
+pub mod assembly;
+pub mod genes;
+pub mod timebins;
+pub mod seqsummary;
+pub mod parents;
+pub mod adapters;
+use panel::Panel;
+use known_sv::KnownSvs;
+use bed::Regions;
+use alt::AltContigs;
+use groups::ContigGroups;
+use genes::GeneModel;
+use seqsummary::{ReadInfo, SeqSummary};
+use parents::SplitReads;
+use timebins::TimeBins;
+use adapters::Adapters;
+use refseq::{Genome, Gaps, add_seq_context, find_inverted_repeat, is_telomeric};
+use std::{ collections::BTreeMap, env, io::{self, Write}};
+
+//breakpoint classes in the order they are counted in break_counts
+//classes after Pass are excluded from the artifact percentages and reported separately
+pub const BREAK_CLASSES: [&str; 11] = ["Foldback", "Chimeric", "Pass", "SystematicArtifact", "KnownSV", "Masked", "InvertedRepeat", "GapSpanning", "ContigEnd", "Spliced", "Palindromic"];
+
+//read level patterns over the ordered segments of a read counted with --read-patterns
+pub const READ_PATTERNS: [&str; 4] = ["Concatemer", "MultiChimera", "Mixed", "Complex"];
+
+// files given on the command line that are loaded once and used to classify every breakpoint
+pub struct Resources {
+    pub panel: Option<Panel>,
+    pub known_svs: Option<KnownSvs>,
+    pub mask: Option<Regions>,
+    pub annot: Vec<(String, Regions)>,
+    pub genome: Option<Genome>,
+    pub gaps: Option<Gaps>,
+    pub alt: Option<AltContigs>,
+    pub groups: Option<ContigGroups>,
+    pub genes: Option<GeneModel>,
+    pub targets: Option<Regions>,
+    pub seq_summary: Option<SeqSummary>,
+    pub adapters: Option<Adapters>,
+}
+
+impl Resources {
+    pub fn load(args: &Cli) -> Resources {
+        let panel = args.panel.as_ref().map(|path| or_exit(Panel::load(path, args.panel_window), "--panel", path));
+        let known_svs = args.known_sv.as_ref().map(|path| or_exit(KnownSvs::load(path, args.known_sv_window), "--known-sv", path));
+        let mask = args.mask.as_ref().map(|path| or_exit(Regions::load(path), "--mask", path));
+        let mut annot = Vec::new(); 
+        for track in &args.annot {
+            //tracks are given as NAME=BED, the file name is used when no name is given
+            let (name, path) = match track.split_once('=') {
+                Some((name, path)) => (name.to_string(), path.to_string()),
+                None => (track.rsplit('/').next().unwrap().split('.').next().unwrap().to_string(), track.to_string()),
+            };
+            annot.push((name, or_exit(Regions::load(&path), "--annot", &path))); 
+        }
+        //the reference is only read when a sequence based option needs it
+        let genome = match &args.genome {
+            Some(path) if args.seq_context || args.inverted_repeats || args.telomere_motif.is_some() => Some(or_exit(Genome::load(path), "--genome", path)),
+            _ => None,
+        };
+        let gaps = match &args.genome {
+            Some(path) if args.gaps => Some(or_exit(Gaps::load(path, args.min_gap), "--genome", path)),
+            _ => None,
+        };
+        let alt = args.alt.as_ref().map(|path| or_exit(AltContigs::load(path), "--alt", path));
+        let groups = args.groups.as_ref().map(|path| or_exit(ContigGroups::load(path), "--groups", path));
+        let genes = args.gtf.as_ref().map(|path| or_exit(GeneModel::load(path), "--gtf", path));
+        let targets = args.targets.as_ref().map(|path| or_exit(Regions::load(path), "--targets", path));
+        let seq_summary = args.seq_summary.as_ref().map(|path| or_exit(SeqSummary::load(path), "--seq-summary", path));
+        Resources{panel, known_svs, mask, annot, genome, gaps, alt, groups, genes,
+            targets, seq_summary, adapters: if args.adapters.is_empty() { None } else { Some(Adapters::load(&args.adapters)) }}
+    }
+}
+
+//a file given on the command line that cannot be read ends the run naming the option and path
+fn or_exit<T>(result: io::Result<T>, option: &str, path: &str) -> T {
+    result.unwrap_or_else(|e| {
+        eprintln!("ERROR: could not read {} {}: {}", option, path, e);
+        std::process::exit(1);
+    })
+}
 
 //let mut break_info = vec![b1.0.to_string(), b1.1.to_string(), directions, b2.0.to_string(), b2.1.to_string(),mapq.to_string(), read_id];  
 
+#[derive(Clone, Default)]
 pub struct Breakpoint {
     pub b1_chr: String, 
     pub b1_loc: i64, 
@@ -18,9 +110,29 @@ pub struct Breakpoint {
     pub label: Option<String>, 
     pub r1_loc: u32, 
     pub r2_loc: u32, 
+    pub b1_annot: Vec<String>, 
+    pub b2_annot: Vec<String>, 
+    pub mh_len: Option<u32>, 
+    pub b1_gc: Option<f32>, 
+    pub b2_gc: Option<f32>, 
+    pub ir_arm: Option<u32>, 
+    pub ir_spacer: Option<u32>, 
+    pub b1_chr_len: i64, 
+    pub b2_chr_len: i64, 
+    pub b1_genes: Vec<(String, char)>, 
+    pub b2_genes: Vec<(String, char)>, 
+    pub b1_feature: Option<String>, 
+    pub b2_feature: Option<String>, 
+    pub read_info: Option<ReadInfo>, 
+    pub adapter: Option<String>, 
+    pub b1_ref: (i64, i64), 
+    pub b2_ref: (i64, i64), 
+    pub b1_arm_len: u32, 
+    pub b2_arm_len: u32, 
+    pub arm_overlap: Option<f32>, 
 }
 impl Breakpoint {
-    pub fn as_tsv(&self, rcoords:&bool) -> String {
+    pub fn as_tsv(&self, args: &Cli) -> String {
         let mut fields = vec![
             self.b1_chr.clone(),
             self.b1_loc.to_string(),
@@ -32,41 +144,497 @@ impl Breakpoint {
             self.label.clone().unwrap_or_else(|| "NA".to_string()),
         ];
 
-        if *rcoords {
+        if args.rcoord {
             fields.push(self.r1_loc.to_string()); 
             fields.push(self.r2_loc.to_string()); 
         }
+        if !args.annot.is_empty() {
+            fields.push(join_or_na(&self.b1_annot)); 
+            fields.push(join_or_na(&self.b2_annot)); 
+        }
+        if args.seq_context {
+            fields.push(self.mh_len.map_or(String::from("NA"), |m| m.to_string())); 
+            fields.push(self.b1_gc.map_or(String::from("NA"), |g| format!("{:.3}", g))); 
+            fields.push(self.b2_gc.map_or(String::from("NA"), |g| format!("{:.3}", g))); 
+        }
+        if args.inverted_repeats {
+            fields.push(self.ir_arm.map_or(String::from("NA"), |a| a.to_string())); 
+            fields.push(self.ir_spacer.map_or(String::from("NA"), |s| s.to_string())); 
+        }
+        if args.scores {
+            fields.push(if is_fold_shape(self, args) { format!("{:.3}", sym_score(self)) } else { String::from("NA") }); 
+            fields.push(confidence(self, args).map_or(String::from("NA"), |c| format!("{:.3}", c))); 
+        }
+        if args.arm_geometry {
+            fields.extend(self.arm_geometry()); 
+        }
+        if args.palindromic {
+            fields.push(self.arm_overlap.map_or(String::from("NA"), |o| format!("{:.3}", o))); 
+        }
+        if !args.adapters.is_empty() {
+            fields.push(self.adapter.clone().unwrap_or_else(|| "NA".to_string())); 
+        }
+        if args.seq_summary.is_some() {
+            match &self.read_info {
+                Some(i) => fields.extend([i.channel.clone(), i.mux.clone(), i.start_time.clone(), i.duration.clone(), i.end_reason.clone(), i.mean_qscore.clone()]),
+                None => fields.extend(vec![String::from("NA"); 6]),
+            }
+        }
+        if args.gtf.is_some() {
+            for (genes, feature) in [(&self.b1_genes, &self.b1_feature), (&self.b2_genes, &self.b2_feature)] {
+                fields.push(join_or_na(&genes.iter().map(|g| g.0.clone()).collect::<Vec<_>>())); 
+                fields.push(join_or_na(&genes.iter().map(|g| g.1.to_string()).collect::<Vec<_>>())); 
+                fields.push(feature.clone().unwrap_or_else(|| "NA".to_string())); 
+            }
+        }
         
         fields.join("\t")
 
     }
+
+    //read space lengths, reference intervals, reference overlap fractions and length ratio of the arms of a foldback
+    fn arm_geometry(&self) -> Vec<String> {
+        if !matches!(self.label.as_deref(), Some("Foldback") | Some("Palindromic")) {
+            return vec![String::from("NA"); 7]; 
+        }
+        let overlap = (self.b1_ref.1.min(self.b2_ref.1) - self.b1_ref.0.max(self.b2_ref.0)).max(0); 
+        let frac = |r: (i64, i64)| if r.1 > r.0 { format!("{:.3}", overlap as f32 / (r.1 - r.0) as f32) } else { String::from("NA") }; 
+        let (short, long) = (self.b1_arm_len.min(self.b2_arm_len), self.b1_arm_len.max(self.b2_arm_len)); 
+        vec![
+            self.b1_arm_len.to_string(),
+            self.b2_arm_len.to_string(),
+            format!("{}:{}-{}", self.b1_chr, self.b1_ref.0, self.b1_ref.1),
+            format!("{}:{}-{}", self.b2_chr, self.b2_ref.0, self.b2_ref.1),
+            frac(self.b1_ref),
+            frac(self.b2_ref),
+            if long > 0 { format!("{:.3}", short as f32 / long as f32) } else { String::from("NA") },
+        ]
+    }
+}
+
+
+//header line of the breakpoint output, matching the optional columns of as_tsv
+pub fn tsv_header(args: &Cli) -> String {
+    let mut header = String::from("#Break1_chr\tBreak1_loc\tBreak_direction\tBreak2_chr\tBreak2_loc\tMapQ\tRead_ID\tClassification"); 
+    if args.rcoord {
+        header.push_str("\tbreak1_read\tbreak2_read"); 
+    }
+    if !args.annot.is_empty() {
+        header.push_str("\tbreak1_annot\tbreak2_annot"); 
+    }
+    if args.seq_context {
+        header.push_str("\tmicrohomology\tbreak1_gc\tbreak2_gc"); 
+    }
+    if args.inverted_repeats {
+        header.push_str("\tir_arm\tir_spacer"); 
+    }
+    if args.scores {
+        header.push_str("\tsym_score\tconfidence"); 
+    }
+    if args.arm_geometry {
+        header.push_str("\tarm1_len\tarm2_len\tarm1_ref\tarm2_ref\tarm1_overlap\tarm2_overlap\tarm_ratio"); 
+    }
+    if args.palindromic {
+        header.push_str("\tarm_overlap"); 
+    }
+    if !args.adapters.is_empty() {
+        header.push_str("\tadapter"); 
+    }
+    if args.seq_summary.is_some() {
+        header.push_str("\tchannel\tmux\tstart_time\tduration\tend_reason\tmean_qscore"); 
+    }
+    if args.gtf.is_some() {
+        header.push_str("\tbreak1_gene\tbreak1_strand\tbreak1_feature\tbreak2_gene\tbreak2_strand\tbreak2_feature"); 
+    }
+    header
+}
+
+fn join_or_na(values: &[String]) -> String {
+    if values.is_empty() {
+        return String::from("NA"); 
+    }
+    values.join(",")
+}
+
+// read and breakpoint counts of one subset of the reads
+#[derive(Clone)]
+pub struct Counts {
+    pub reads_pass_filter: u64, 
+    pub read_counts: Vec<u64>, //[fold, chim, pass, mixed, palindromic]
+    pub break_counts: Vec<u64>, //counts of BREAK_CLASSES
+}
+
+impl Default for Counts {
+    fn default() -> Counts {
+        Counts::new()
+    }
 }
 
+impl Counts {
+    pub fn new() -> Counts {
+        Counts{reads_pass_filter: 0, read_counts: vec![0; 5], break_counts: vec![0; BREAK_CLASSES.len()]}
+    }
+
+    //add a read passing the filters with its classified breakpoints
+    pub fn add_read(&mut self, breaks: &[Breakpoint], args: &Cli) {
+        self.reads_pass_filter +=1; 
+        if breaks.is_empty() {
+            return; 
+        }
+        for b in breaks {
+            self.break_counts[class_index(b.label.as_deref().unwrap_or("Pass"))] +=1; 
+        }
+        read_level_class(breaks, args, &mut self.read_counts); 
+    }
+
+    //reads and breakpoints with their percentages as [reads, fold reads, %, fold breaks, %, chim reads, %, chim breaks, %]
+    fn stats(&self) -> Vec<String> {
+        let counted_breaks = self.break_counts[..3].iter().sum::<u64>(); 
+        let mut out = vec![self.reads_pass_filter.to_string()]; 
+        for i in 0..2 {
+            out.push(self.read_counts[i].to_string()); 
+            out.push(get_percent(self.read_counts[i], self.reads_pass_filter) + "%"); 
+            out.push(self.break_counts[i].to_string()); 
+            out.push(get_percent(self.break_counts[i], counted_breaks) + "%"); 
+        }
+        out
+    }
+
+    //columns of the --tabular summary up to Chim_breaks% followed by the excluded class counts
+    fn table_stats(&self, excluded: &[usize]) -> Vec<String> {
+        let s = self.stats(); 
+        let mut out = vec![s[0].clone(), self.break_counts.iter().sum::<u64>().to_string(), self.read_counts.iter().sum::<u64>().to_string()]; 
+        out.extend_from_slice(&s[1..]); 
+        for i in excluded {
+            out.push(self.break_counts[*i].to_string()); 
+        }
+        out
+    }
+}
+
+// counts collected over a run and printed by print_report or print_table
+pub struct Summary {
+    pub reads_pass_filter: u64, 
+    pub read_counts: Vec<u64>, //[fold, chim, pass, mixed, palindromic]
+    pub break_counts: Vec<u64>, //counts of BREAK_CLASSES
+    pub annot_counts: Vec<(String, Vec<u64>)>, //per --annot track [fold one end, fold both ends, chim one end, chim both ends]
+    pub group_pairs: BTreeMap<(String, String), u64>, //chimeric breakpoints per pair of --groups contig groups
+    pub gene_pairs: BTreeMap<(String, String), Vec<u64>>, //chimeric breakpoints joining two --gtf genes [all, both ends at exon boundaries]
+    pub target_counts: Vec<Counts>, //[on-target, off-target] reads of --targets
+    pub tag_groups: BTreeMap<String, Counts>, //reads by their --group-by value
+    pub time_bins: TimeBins, //reads by --bin-minutes bin of their start time
+    pub end_reasons: BTreeMap<String, Counts>, //reads by end_reason of --seq-summary
+    pub split_reads: SplitReads, //children of reads split by the basecaller for --split-parents
+    pub adapter_breaks: Vec<u64>, //[fold, chim] breakpoints with an --adapters sequence at the junction
+    pub pattern_counts: Vec<u64>, //reads of each READ_PATTERNS pattern
+}
+
+impl Summary {
+    pub fn new(res: &Resources) -> Summary {
+        Summary{reads_pass_filter: 0, read_counts: vec![0; 5], break_counts: vec![0; BREAK_CLASSES.len()],
+            annot_counts: res.annot.iter().map(|(name, _)| (name.clone(), vec![0; 4])).collect(), group_pairs: BTreeMap::new(),
+            gene_pairs: BTreeMap::new(), target_counts: vec![Counts::new(); 2],
+            tag_groups: BTreeMap::new(), time_bins: TimeBins::new(),
+            end_reasons: BTreeMap::new(), split_reads: SplitReads::new(),
+            adapter_breaks: vec![0; 2], pattern_counts: vec![0; READ_PATTERNS.len()]}
+    }
+
+    //add the breakpoint to the annotation, contig group and gene pair counts
+    pub fn add_break(&mut self, brk: &Breakpoint, res: &Resources) {
+        self.add_annotations(brk); 
+        self.add_gene_pair(brk); 
+        if brk.adapter.is_some() {
+            match brk.label.as_deref() {
+                Some("Foldback") => self.adapter_breaks[0] +=1,
+                Some("Chimeric") => self.adapter_breaks[1] +=1,
+                _ => {}
+            }
+        }
+        if let Some(groups) = &res.groups {
+            if brk.label.as_deref() == Some("Chimeric") {
+                let g1 = groups.group_of(&brk.b1_chr).to_string(); 
+                let g2 = groups.group_of(&brk.b2_chr).to_string(); 
+                let pair = if g1 <= g2 { (g1, g2) } else { (g2, g1) }; 
+                *self.group_pairs.entry(pair).or_insert(0) +=1; 
+            }
+        }
+    }
+
+    //count the pattern of the segment chain of a read with its breakpoints in read order
+    pub fn add_read_pattern(&mut self, breaks: &[Breakpoint], args: &Cli) {
+        if let Some(i) = read_pattern(breaks, args) {
+            self.pattern_counts[i] +=1; 
+        }
+    }
+
+    //counts of all reads
+    pub fn totals(&self) -> Counts {
+        Counts{reads_pass_filter: self.reads_pass_filter, read_counts: self.read_counts.clone(), break_counts: self.break_counts.clone()}
+    }
+
+    //chimeric breakpoints within one contig group and across two groups
+    pub fn group_split(&self) -> (u64, u64) {
+        let intra = self.group_pairs.iter().filter(|((a, b), _)| a == b).map(|(_, n)| n).sum::<u64>(); 
+        let inter = self.group_pairs.values().sum::<u64>() - intra; 
+        (intra, inter)
+    }
+
+    //count chimeric junctions between two different genes as candidate fusions
+    fn add_gene_pair(&mut self, brk: &Breakpoint) {
+        if brk.label.as_deref() != Some("Chimeric") {
+            return; 
+        }
+        let (g1, g2) = match (brk.b1_genes.first(), brk.b2_genes.first()) {
+            (Some(g1), Some(g2)) => (g1.0.clone(), g2.0.clone()), 
+            _ => return,
+        }; 
+        //chimeras within one gene are not fusions
+        if brk.b1_genes.iter().any(|g| brk.b2_genes.contains(g)) {
+            return; 
+        }
+        let pair = if g1 <= g2 { (g1, g2) } else { (g2, g1) }; 
+        let counts = self.gene_pairs.entry(pair).or_insert(vec![0; 2]); 
+        counts[0] +=1; 
+        if brk.b1_feature.as_deref() == Some("exon_boundary") && brk.b2_feature.as_deref() == Some("exon_boundary") {
+            counts[1] +=1; 
+        }
+    }
+
+    //count artifact junctions by whether one or both ends fall in each annotation track
+    fn add_annotations(&mut self, brk: &Breakpoint) {
+        let offset = match brk.label.as_deref() {
+            Some("Foldback") => 0,
+            Some("Chimeric") => 2,
+            _ => return,
+        }; 
+        for (name, counts) in self.annot_counts.iter_mut() {
+            let ends = brk.b1_annot.contains(name) as usize + brk.b2_annot.contains(name) as usize; 
+            if ends > 0 {
+                counts[offset + ends - 1] +=1; 
+            }
+        }
+    }
+}
+
+//find the annotation tracks each end of the breakpoint falls in and its reference sequence context
+pub fn annotate_break(brk: &mut Breakpoint, args: &Cli, res: &Resources) {
+    for (name, regions) in &res.annot {
+        if regions.contains(&brk.b1_chr, brk.b1_loc) {
+            brk.b1_annot.push(name.clone()); 
+        }
+        if regions.contains(&brk.b2_chr, brk.b2_loc) {
+            brk.b2_annot.push(name.clone()); 
+        }
+    }
+    if let Some(genes) = &res.genes {
+        (brk.b1_genes, brk.b1_feature) = genes.annotate(&brk.b1_chr, brk.b1_loc, args.splice_tol); 
+        (brk.b2_genes, brk.b2_feature) = genes.annotate(&brk.b2_chr, brk.b2_loc, args.splice_tol); 
+    }
+    if let Some(genome) = &res.genome {
+        if args.seq_context {
+            add_seq_context(brk, genome, args.flank); 
+        }
+        //scan the reference around junctions with foldback geometry for inverted repeats
+        if args.inverted_repeats && is_fold_shape(brk, args) {
+            let center = (brk.b1_loc + brk.b2_loc) / 2; 
+            if let Some((arm, spacer)) = find_inverted_repeat(genome, &brk.b1_chr, center, args.ir_window, args.max_ir_spacer) {
+                brk.ir_arm = Some(arm); 
+                brk.ir_spacer = Some(spacer); 
+            }
+        }
+    }
+}
+
+//reference overlap of the two arms of an opposite strand junction on one contig, as a fraction of the longer arm
+pub fn arm_overlap(brk: &Breakpoint) -> Option<f32> {
+    if brk.b1_chr != brk.b2_chr || !(brk.directions == "<>" || brk.directions == "><") {
+        return None; 
+    }
+    let overlap = (brk.b1_ref.1.min(brk.b2_ref.1) - brk.b1_ref.0.max(brk.b2_ref.0)).max(0); 
+    let longer = (brk.b1_ref.1 - brk.b1_ref.0).max(brk.b2_ref.1 - brk.b2_ref.0); 
+    if longer <= 0 {
+        return None; 
+    }
+    Some(overlap as f32 / longer as f32)
+}
+
+//arms covering nearly the same reference interval across almost the whole read
+fn is_palindromic(brk: &Breakpoint, args: &Cli) -> bool {
+    let read_frac = (brk.b1_arm_len + brk.b2_arm_len) as f32 / brk.read_len as f32; 
+    brk.arm_overlap.is_some_and(|o| o >= args.palindrome_overlap) && read_frac >= args.palindrome_overlap
+}
+
+//index in READ_PATTERNS of a read with more than one breakpoint, None for other reads
+fn read_pattern(breaks: &[Breakpoint], args: &Cli) -> Option<usize> {
+    //reads with more than --max-segments segments are complex whatever their junctions
+    if breaks.len() + 1 > args.max_segments {
+        return Some(3); 
+    }
+    if breaks.len() < 2 {
+        return None; 
+    }
+    //alternating strand segments folding back onto one locus
+    if breaks.iter().all(|b| is_fold_shape(b, args)) {
+        return Some(0); 
+    }
+    let fold = breaks.iter().filter(|b| b.label.as_deref() == Some("Foldback")).count(); 
+    let chim = breaks.iter().filter(|b| b.label.as_deref() == Some("Chimeric")).count(); 
+    if fold > 0 && chim > 0 {
+        return Some(2); 
+    }
+    if chim > 1 {
+        return Some(1); 
+    }
+    None
+}
+
+//opposite strands of the same contig within the foldback distance
+fn is_fold_shape(brk: &Breakpoint, args: &Cli) -> bool {
+    let dist = break_dist(brk, args); 
+    brk.b1_chr == brk.b2_chr && (brk.directions == "<>" || brk.directions == "><") && dist <= args.fold
+}
 
 //classify break as either chimeric, foldback, or pass
-pub fn classify_break(brk: &Breakpoint,  args: &Cli, break_counts: &mut Vec<u64>) -> String {
-    let dist = (brk.b2_loc - brk.b1_loc).abs() as i32; 
-    if brk.b1_chr != brk.b2_chr{
-        break_counts[1] +=1; 
-        return String::from("Chimeric"); 
+pub fn classify_break(brk: &Breakpoint,  args: &Cli, res: &Resources, break_counts: &mut Vec<u64>) -> String {
+    let label = get_label(brk, args, res); 
+    break_counts[class_index(&label)] +=1; 
+    label
+}
+
+//distance between the two ends, taken around the origin when shorter for circular contigs
+fn break_dist(brk: &Breakpoint, args: &Cli) -> i32 {
+    let dist = (brk.b2_loc - brk.b1_loc).abs(); 
+    if brk.b1_chr == brk.b2_chr && brk.b1_chr_len > 0 && args.circular.contains(&brk.b1_chr) {
+        return dist.min(brk.b1_chr_len - dist) as i32; 
+    }
+    dist as i32
+}
+
+fn get_label(brk: &Breakpoint,  args: &Cli, res: &Resources) -> String {
+    //breaks in excluded regions are dominated by misalignment 
+    if let Some(mask) = &res.mask {
+        if mask.contains(&brk.b1_chr, brk.b1_loc) || mask.contains(&brk.b2_chr, brk.b2_loc) {
+            return String::from("Masked"); 
+        }
+    }
+
+    //known rearrangements of the sample are real junctions, not artifacts
+    if let Some(known) = &res.known_svs {
+        if known.contains(brk) {
+            return String::from("KnownSV"); 
+        }
+    }
+
+    //reads crossing an assembly gap are split around it
+    if let Some(gaps) = &res.gaps {
+        if gaps.spans(brk, args.gap_tol) {
+            return String::from("GapSpanning"); 
+        }
+    }
+
+    //split alignments of a spliced read joining consecutive exons are not breaks
+    if let Some(genes) = &res.genes {
+        if genes.is_splice_junction(brk, args.splice_tol) {
+            return String::from("Spliced"); 
+        }
+    }
+
+    //junctions between an ALT contig and its primary region are classified on primary coordinates
+    let lifted = res.alt.as_ref().and_then(|alt| alt.lift_break(brk)); 
+    let geom = lifted.as_ref().unwrap_or(brk); 
+
+    let dist = break_dist(geom, args); 
+    let label = if geom.b1_chr != geom.b2_chr{
+        "Chimeric"
     } else if dist >= args.chim {
-        break_counts[1] +=1; 
-        return String::from("Chimeric"); 
-    } else if (brk.directions == "<>" || brk.directions == "><") && dist <= args.fold {
-        if args.no_sym {
-              break_counts[0] +=1; 
-            return String::from("Foldback"); 
+        "Chimeric"
+    } else if (geom.directions == "<>" || geom.directions == "><") && dist <= args.fold {
+        if args.palindromic && is_palindromic(brk, args) {
+            //read-through hairpins of duplex follow-on reads or molecules missing an adapter
+            "Palindromic"
+        } else if args.no_sym || check_sym(brk, args) {
+            //folds at reference palindromes can be genuine hairpin structures
+            if brk.ir_arm.is_some_and(|arm| arm >= args.min_ir_arm) { "InvertedRepeat" } else { "Foldback" }
         } else {
-            return check_sym(brk, args, break_counts); 
+            "Pass"
+        }
+    } else {
+        "Pass"
+    }; 
+
+    //artifacts at contig ends or telomeric repeats are not library artifacts
+    if label != "Pass" && (near_contig_end(brk, args) || near_telomere(brk, args, res)) {
+        return String::from("ContigEnd"); 
+    }
+
+    //artifact junctions that recur across the panel of normals are reported as systematic
+    if label != "Pass" {
+        if let Some(p) = &res.panel {
+            if p.is_systematic(brk, args.panel_min_samples) {
+                return String::from("SystematicArtifact"); 
+            }
+        }
+    }
+    String::from(label)
+}
+
+//either end within --end-dist of the start or end of its contig
+fn near_contig_end(brk: &Breakpoint, args: &Cli) -> bool {
+    let Some(end_dist) = args.end_dist else {
+        return false; 
+    };
+    let near = |loc: i64, len: i64| loc <= end_dist || (len > 0 && len - loc <= end_dist); 
+    near(brk.b1_loc, brk.b1_chr_len) || near(brk.b2_loc, brk.b2_chr_len)
+}
+
+//either end in reference telomeric repeats of --telomere-motif
+fn near_telomere(brk: &Breakpoint, args: &Cli, res: &Resources) -> bool {
+    match (&args.telomere_motif, &res.genome) {
+        (Some(motif), Some(genome)) => {
+            let motif = motif.to_ascii_uppercase(); 
+            is_telomeric(genome, &brk.b1_chr, brk.b1_loc, motif.as_bytes()) || is_telomeric(genome, &brk.b2_chr, brk.b2_loc, motif.as_bytes())
         }
-    } 
+        _ => false,
+    }
+}
 
-    break_counts[2] +=1; 
-    return String::from("Pass"); 
+//distance of the break from the middle of the read as a fraction of the read length, check_sym passes within --margin
+fn sym_score(brk: &Breakpoint) -> f32 {
+    let rlen = brk.read_len as f32; 
+    //the fold is taken at the start of the second segment in read coordinates, as in check_sym
+    let r_break = brk.r2_loc as f32; 
+    (r_break - rlen / 2.0).abs() / rlen
+}
+
+//confidence in the label of the breakpoint in [0, 1] from the MAPQ of the weaker flank, the length of the shorter segment
+//and how far the junction is from the thresholds of its class, None for classes not decided by these thresholds
+fn confidence(brk: &Breakpoint, args: &Cli) -> Option<f32> {
+    let dist = break_dist(brk, args) as f32; 
+    let margin = |v: f32| v.clamp(0.0, 1.0); 
+    //zero thresholds given on the command line would divide by zero
+    let chim = args.chim.max(1) as f32; 
+    let sym_margin = args.margin.max(f32::EPSILON); 
+    let threshold = match brk.label.as_deref()? {
+        "Chimeric" if brk.b1_chr != brk.b2_chr => 1.0,
+        "Chimeric" => margin((dist - chim) / chim),
+        "Foldback" | "Palindromic" => {
+            let fold = margin(1.0 - dist / (args.fold.max(0) as f32 + 1.0)); 
+            if args.no_sym { fold } else { fold.min(margin(1.0 - sym_score(brk) / sym_margin)) }
+        }
+        //passing fold shaped junctions failed the symmetry test, other passing junctions are short of the chimeric distance
+        "Pass" if is_fold_shape(brk, args) => margin(sym_score(brk) / sym_margin - 1.0),
+        "Pass" if brk.b1_chr == brk.b2_chr => margin((chim - dist) / chim),
+        _ => return None,
+    }; 
+    let mapq = brk.mapq.min(60) as f32 / 60.0; 
+    //segments reach full weight at twice the minimum alignment length
+    let min_len = args.min_map_len.max(1) as f32; 
+    let seg = margin((brk.b1_arm_len.min(brk.b2_arm_len) as f32 - min_len) / min_len); 
+    Some(mapq * seg * threshold)
 }
 
 // fucntion to check whether the foldback artifact occurs nearly in the middle of the read 
-fn check_sym(brk:&Breakpoint, args: &Cli, break_counts: &mut Vec<u64>) -> String {
+fn check_sym(brk:&Breakpoint, args: &Cli) -> bool {
     //consider symetric read if break occurs +/- 5% of middle of read 
     let rlen = brk.read_len as f32; 
     let r1 = brk.r2_loc as f32;
@@ -77,61 +645,322 @@ fn check_sym(brk:&Breakpoint, args: &Cli, break_counts: &mut Vec<u64>) -> String
 
     let range_min = rlen/2.0 - (args.margin * rlen); 
     let range_max = rlen/2.0 + (args.margin * rlen); 
-    if range_min <= r_break_ave  && r_break_ave <= range_max {
-        break_counts[0] +=1; 
-        return String::from("Foldback");
-    } else {
-        break_counts[2] +=1; 
-        return String::from("Pass"); 
-    }
+    range_min <= r_break_ave  && r_break_ave <= range_max
 } 
 
+//position of a breakpoint label in break_counts
+pub fn class_index(label: &str) -> usize {
+    BREAK_CLASSES.iter().position(|c| *c == label).expect("unknown breakpoint class")
+}
+
+//excluded breakpoint classes whose option was given on the command line
+fn excluded_classes(args: &Cli) -> Vec<usize> {
+    let mut out = Vec::new(); 
+    if args.panel.is_some() {
+        out.push(class_index("SystematicArtifact")); 
+    }
+    if args.known_sv.is_some() {
+        out.push(class_index("KnownSV")); 
+    }
+    if args.mask.is_some() {
+        out.push(class_index("Masked")); 
+    }
+    if args.inverted_repeats {
+        out.push(class_index("InvertedRepeat")); 
+    }
+    if args.gaps {
+        out.push(class_index("GapSpanning")); 
+    }
+    if args.end_dist.is_some() || args.telomere_motif.is_some() {
+        out.push(class_index("ContigEnd")); 
+    }
+    if args.gtf.is_some() {
+        out.push(class_index("Spliced")); 
+    }
+    if args.palindromic {
+        out.push(class_index("Palindromic")); 
+    }
+    out
+}
+
 //get the read level classification based on the classification of the breakpoints in the read and update all counts 
-pub fn read_level_class(label_counts: &Vec<u32>, cur_counts: &mut Vec<u64>) {
+pub fn read_level_class(breaks: &[Breakpoint], args: &Cli, cur_counts: &mut Vec<u64>) {
+    let labels: Vec<&str> = breaks.iter().map(|b| b.label.as_deref().unwrap_or("Pass")).collect(); 
+    cur_counts[policy_class(&labels, args)] +=1; 
+}
+
+//index of the read level classification [fold, chim, pass, mixed, palindromic] of the breakpoint labels of a read in read order with --read-policy
+fn policy_class(labels: &[&str], args: &Cli) -> usize {
+    let mut label_counts: Vec<u32> = vec![0; 3]; 
+    let mut palindromic = 0; 
+    for label in labels {
+        match *label {
+            "Foldback" => label_counts[0] += 1,
+            "Chimeric" => label_counts[1] += 1,
+            "Palindromic" => {
+                palindromic += 1; 
+                label_counts[2] += 1; 
+            }
+            _ => label_counts[2] += 1,
+        }
+    }
+    let artifacts = label_counts[0] + label_counts[1] + palindromic; 
+    let class = match args.read_policy {
+        ReadPolicy::Majority => read_class(&label_counts),
+        ReadPolicy::FirstBreakpoint => return match labels.first() {
+            Some(&"Foldback") => 0,
+            Some(&"Chimeric") => 1,
+            Some(&"Palindromic") => 4,
+            _ => 2,
+        },
+        ReadPolicy::AnyChimera if label_counts[1] > 0 => 1,
+        ReadPolicy::AnyChimera => read_class(&label_counts),
+        ReadPolicy::MinFraction if (artifacts as f32) < args.min_artifact_frac * labels.len() as f32 => return 2,
+        ReadPolicy::MinFraction => read_class(&label_counts),
+        ReadPolicy::Mixed if label_counts[0] > 0 && label_counts[1] > 0 => 3,
+        ReadPolicy::Mixed => read_class(&label_counts),
+    }; 
+    //reads whose only artifact breakpoints are read-through hairpins are counted apart from other foldbacks
+    if class == 2 && palindromic > 0 {
+        return 4; 
+    }
+    class
+}
+
+//index of the read level classification [fold, chim, pass] from the counts of its breakpoint labels
+pub fn read_class(label_counts: &Vec<u32>) -> usize {
     //if only pass breaks found, read is not artifact
     if label_counts[2] > 0 && (label_counts[0] + label_counts[1] == 0){
-        cur_counts[2] +=1; 
+        2
     } else if label_counts[0] >=  label_counts[1]{ 
         //more folds breaks
         //if even one fold or chim found, read is artifact and classified by 
         //which one was more frequent, a tie goes to fold
-        cur_counts[0] +=1; 
+        0
     }else {
         //more chim breaks
-        cur_counts[1] +=1; 
+        1
     }
 }
 
 
 //print summary statistics to stdout
-pub fn print_report(reads_pass_filter: u64, read_counts: &Vec<u64>, break_counts: &Vec<u64>, args: &Cli ) -> io::Result<()>   {
+pub fn print_report(summary: &Summary, args: &Cli ) -> io::Result<()>   {
+    let reads_pass_filter = summary.reads_pass_filter; 
+    let read_counts = &summary.read_counts; 
+    let break_counts = &summary.break_counts; 
+    let counted_breaks = break_counts[..3].iter().sum::<u64>(); 
     let stdout = io::stdout();
     let mut handle = stdout.lock();
     writeln!(handle, "{}", "*".repeat(100))?;
     writeln!(handle, "Breakinator summary report:")?;
     writeln!(handle, "Command: {}", env::args().collect::<Vec<_>>().join(" "))?;
     writeln!(handle, "Filtering Criteria: MapQ \u{2265} {} and min_alignment_len \u{2265} {}", args.min_mapq, args.min_map_len)?;
+    writeln!(handle, "Read level policy: {}", policy_description(args))?;
     writeln!(handle, "\nResults\n{}", "-".repeat(20))? ;
     writeln!(handle, "Num reads passed filter: {}", add_commas(reads_pass_filter))? ;
     writeln!(handle, "Num breakpoints detected: {} on {} unique reads" , add_commas(break_counts.iter().sum::<u64>()), add_commas(read_counts.iter().sum::<u64>()))? ;
     writeln!(handle, "\nFoldback artifacts:")? ; 
     writeln!(handle, "Num Foldback READS detected: {}  ({}% of all reads)" , add_commas(read_counts[0]), get_percent(read_counts[0], reads_pass_filter))? ;
-    writeln!(handle, "Num Foldback BREAKPOINTS detected: {}  ({}% of all breakpoints)" , add_commas(break_counts[0]), get_percent(break_counts[0] ,  break_counts.iter().sum::<u64>()))? ;
+    writeln!(handle, "Num Foldback BREAKPOINTS detected: {}  ({}% of all breakpoints)" , add_commas(break_counts[0]), get_percent(break_counts[0] , counted_breaks))? ;
     writeln!(handle, "\nChimeric artifacts:")? ; 
     writeln!(handle, "Num Chimeric READS detected: {}  ({}% of all reads)" , add_commas(read_counts[1]), get_percent(read_counts[1], reads_pass_filter))? ;
-    writeln!(handle, "Num chimeric BREAKPOINTS detected: {}  ({}% of all breakpoints)" , add_commas(break_counts[1]), get_percent(break_counts[1], break_counts.iter().sum::<u64>()))? ;
+    writeln!(handle, "Num chimeric BREAKPOINTS detected: {}  ({}% of all breakpoints)" , add_commas(break_counts[1]), get_percent(break_counts[1], counted_breaks))? ;
+    if args.read_policy == ReadPolicy::Mixed {
+        writeln!(handle, "\nMixed artifacts (--read-policy mixed):")? ; 
+        writeln!(handle, "Num Mixed policy READS detected: {}  ({}% of all reads)" , add_commas(read_counts[3]), get_percent(read_counts[3], reads_pass_filter))? ;
+    }
+    if args.palindromic {
+        writeln!(handle, "\nPalindromic read-throughs:")? ; 
+        writeln!(handle, "Num Palindromic READS detected: {}  ({}% of all reads)" , add_commas(read_counts[4]), get_percent(read_counts[4], reads_pass_filter))? ;
+    }
+    let excluded = excluded_classes(args); 
+    if !excluded.is_empty() {
+        writeln!(handle, "\nExcluded breakpoints (not counted in percentages):")? ; 
+        for i in excluded {
+            writeln!(handle, "Num {} BREAKPOINTS detected: {}" , BREAK_CLASSES[i], add_commas(break_counts[i]))? ;
+        }
+    }
+    if !summary.annot_counts.is_empty() {
+        writeln!(handle, "\nArtifact BREAKPOINTS by annotation (one end / both ends):")? ; 
+        for (name, counts) in &summary.annot_counts {
+            writeln!(handle, "{}: Foldback {} / {}, Chimeric {} / {}", name, add_commas(counts[0]), add_commas(counts[1]), add_commas(counts[2]), add_commas(counts[3]))? ;
+        }
+    }
+    if args.groups.is_some() {
+        let (intra, inter) = summary.group_split(); 
+        writeln!(handle, "\nChimeric BREAKPOINTS by contig group:")? ; 
+        writeln!(handle, "Num intra-group chimeric BREAKPOINTS: {}  ({}% of chimeric breakpoints)", add_commas(intra), get_percent(intra, break_counts[1]))? ;
+        writeln!(handle, "Num inter-group chimeric BREAKPOINTS: {}  ({}% of chimeric breakpoints)", add_commas(inter), get_percent(inter, break_counts[1]))? ;
+        for ((g1, g2), n) in &summary.group_pairs {
+            writeln!(handle, "{} - {}: {}", g1, g2, add_commas(*n))? ;
+        }
+    }
+    if args.targets.is_some() {
+        writeln!(handle, "\nOn-target and off-target reads:")? ; 
+        for (name, counts) in ["On-target", "Off-target"].iter().zip(&summary.target_counts) {
+            write_counts(&mut handle, name, counts)?; 
+        }
+    }
+    if args.seq_summary.is_some() {
+        writeln!(handle, "\nReads by end reason:")? ; 
+        for (name, counts) in &summary.end_reasons {
+            write_counts(&mut handle, name, counts)?; 
+        }
+    }
+    if !args.adapters.is_empty() {
+        writeln!(handle, "\nArtifact BREAKPOINTS with an adapter at the junction:")? ; 
+        writeln!(handle, "Num Foldback BREAKPOINTS with adapter: {}  ({}% of foldback breakpoints)", add_commas(summary.adapter_breaks[0]), get_percent(summary.adapter_breaks[0], break_counts[0]))? ;
+        writeln!(handle, "Num adapter-mediated chimeric BREAKPOINTS: {}  ({}% of chimeric breakpoints)", add_commas(summary.adapter_breaks[1]), get_percent(summary.adapter_breaks[1], break_counts[1]))? ;
+    }
+    if args.read_patterns {
+        writeln!(handle, "\nRead patterns over all segments:")? ; 
+        for (name, n) in READ_PATTERNS.iter().zip(&summary.pattern_counts) {
+            writeln!(handle, "Num {} READS: {}  ({}% of all reads)", name, add_commas(*n), get_percent(*n, summary.reads_pass_filter))? ;
+        }
+    }
+    if args.split_parents {
+        let split = &summary.split_reads; 
+        let (adjacent, distant) = split.split_junctions(args.parent_window); 
+        writeln!(handle, "\nReads split by the basecaller:")? ; 
+        writeln!(handle, "Num split parent READS: {} into {} children", add_commas(split.num_parents()), add_commas(split.children.reads_pass_filter))? ;
+        for (n, parents) in split.children_per_parent() {
+            writeln!(handle, "Parents split into {} children: {}", n, add_commas(parents))? ;
+        }
+        writeln!(handle, "Splits between adjacently mapped children (contiguous molecule split): {}", add_commas(adjacent))? ;
+        writeln!(handle, "Splits between distant children (junction removed by splitting): {}", add_commas(distant))? ;
+        write_counts(&mut handle, "Children", &split.children)?; 
+    }
+    if let Some(tag) = &args.group_by {
+        writeln!(handle, "\nReads by {}:", tag)? ; 
+        for (name, counts) in &summary.tag_groups {
+            write_counts(&mut handle, name, counts)?; 
+        }
+    }
+    if args.gtf.is_some() {
+        let fusions = summary.gene_pairs.values().map(|c| c[0]).sum::<u64>(); 
+        writeln!(handle, "\nChimeric BREAKPOINTS joining two genes:")? ; 
+        writeln!(handle, "Num gene pair chimeric BREAKPOINTS: {}  ({}% of chimeric breakpoints)", add_commas(fusions), get_percent(fusions, break_counts[1]))? ;
+        writeln!(handle, "Gene pair\tBreakpoints\tAt_exon_boundaries")? ;
+        //most supported gene pairs first
+        let mut pairs: Vec<_> = summary.gene_pairs.iter().collect(); 
+        pairs.sort_by(|a, b| b.1[0].cmp(&a.1[0])); 
+        for ((g1, g2), counts) in pairs {
+            writeln!(handle, "{}--{}\t{}\t{}", g1, g2, add_commas(counts[0]), add_commas(counts[1]))? ;
+        }
+    }
     writeln!(handle, "{}", "*".repeat(100))?;
     return Ok(())
 }
 
+//precedence rule of --read-policy recorded in the report
+fn policy_description(args: &Cli) -> String {
+    match args.read_policy {
+        ReadPolicy::Majority => String::from("majority (any artifact breakpoint makes an artifact read, ties go to Foldback)"),
+        ReadPolicy::FirstBreakpoint => String::from("first-breakpoint (class of the first breakpoint in read order)"),
+        ReadPolicy::AnyChimera => String::from("any-chimera (any chimeric breakpoint makes a chimeric read, otherwise majority)"),
+        ReadPolicy::MinFraction => format!("min-fraction (artifact read when \u{2265} {} of breakpoints are artifacts, then majority)", args.min_artifact_frac),
+        ReadPolicy::Mixed => String::from("mixed (reads with foldback and chimeric breakpoints are Mixed, otherwise majority)"),
+    }
+}
+
+//--read-policy value recorded in the --tabular output, with its threshold for min-fraction
+fn policy_name(args: &Cli) -> String {
+    let name = args.read_policy.to_possible_value().expect("read policies have a name").get_name().to_string(); 
+    if args.read_policy == ReadPolicy::MinFraction {
+        return format!("{}:{}", name, args.min_artifact_frac); 
+    }
+    name
+}
+
+//one line of read and breakpoint artifact rates for a subset of the reads
+fn write_counts(handle: &mut impl Write, name: &str, counts: &Counts) -> io::Result<()> {
+    let s = counts.stats(); 
+    writeln!(handle, "{}: {} reads, Foldback READS {} ({}), Foldback BREAKPOINTS {} ({}), Chimeric READS {} ({}), Chimeric BREAKPOINTS {} ({})",
+        name, add_commas(counts.reads_pass_filter), s[1], s[2], s[3], s[4], s[5], s[6], s[7], s[8])
+}
+
 //print summary statistics to stdout in table format
-pub fn print_table(reads_pass_filter: u64, read_counts: &Vec<u64>, break_counts: &Vec<u64>, file_name: String ) -> io::Result<()>   {
+pub fn print_table(summary: &Summary, args: &Cli ) -> io::Result<()>   {
     let stdout = io::stdout();
     let mut handle = stdout.lock();
-    let tot_breaks = break_counts.iter().sum::<u64>(); 
-    let all_stats = vec![reads_pass_filter.to_string(), tot_breaks.to_string(), read_counts.iter().sum::<u64>().to_string(), read_counts[0].to_string(), get_percent(read_counts[0],reads_pass_filter) + "%", break_counts[0].to_string(), get_percent(break_counts[0],tot_breaks)+ "%", read_counts[1].to_string(), get_percent(read_counts[1],reads_pass_filter)+ "%", break_counts[1].to_string(), get_percent(break_counts[1],tot_breaks) + "%", file_name]; 
-    writeln!(handle,"#Reads_passed\tall_break\tUniq_artifact_reads\tFold_reads\tFold_reads%\tFold_breaks\tFold_breaks%\tChim_reads\tChim_reads%\tChim_breaks\tChim_breaks%\tsample")?; 
+    let excluded = excluded_classes(args); 
+    let mut all_stats = summary.totals().table_stats(&excluded); 
+    let mut header = String::from("#Reads_passed\tall_break\tUniq_artifact_reads\tFold_reads\tFold_reads%\tFold_breaks\tFold_breaks%\tChim_reads\tChim_reads%\tChim_breaks\tChim_breaks%"); 
+    for i in &excluded {
+        header.push_str(&format!("\t{}_breaks", BREAK_CLASSES[*i])); 
+    }
+    //rows of --group-by groups have the read and breakpoint class counts, the columns of other options are NA
+    let mut group_rows: Vec<Vec<String>> = summary.tag_groups.values().map(|c| c.table_stats(&excluded)).collect(); 
+    let totals = summary.totals(); 
+    let mut read_classes = Vec::new(); 
+    if args.read_policy == ReadPolicy::Mixed {
+        read_classes.push((3, "Mixed_policy_reads")); 
+    }
+    if args.palindromic {
+        read_classes.push((4, "Palindromic_reads")); 
+    }
+    for (i, name) in read_classes {
+        header.push_str(&format!("\t{}\t{}%", name, name)); 
+        let rows = std::iter::once((&mut all_stats, &totals)).chain(group_rows.iter_mut().zip(summary.tag_groups.values())); 
+        for (row, counts) in rows {
+            row.push(counts.read_counts[i].to_string()); 
+            row.push(get_percent(counts.read_counts[i], counts.reads_pass_filter) + "%"); 
+        }
+    }
+    if args.groups.is_some() {
+        let (intra, inter) = summary.group_split(); 
+        header.push_str("\tChim_intra_group\tChim_inter_group"); 
+        all_stats.push(intra.to_string()); 
+        all_stats.push(inter.to_string()); 
+    }
+    if args.targets.is_some() {
+        for (prefix, counts) in ["On_target", "Off_target"].iter().zip(&summary.target_counts) {
+            for col in ["reads", "Fold_reads", "Fold_reads%", "Fold_breaks", "Fold_breaks%", "Chim_reads", "Chim_reads%", "Chim_breaks", "Chim_breaks%"] {
+                header.push_str(&format!("\t{}_{}", prefix, col)); 
+            }
+            all_stats.extend(counts.stats()); 
+        }
+    }
+    if !args.adapters.is_empty() {
+        header.push_str("\tFold_adapter_breaks\tChim_adapter_breaks"); 
+        all_stats.extend(summary.adapter_breaks.iter().map(|n| n.to_string())); 
+    }
+    if args.read_patterns {
+        for (name, n) in READ_PATTERNS.iter().zip(&summary.pattern_counts) {
+            header.push_str(&format!("\t{}_reads", name)); 
+            all_stats.push(n.to_string()); 
+        }
+    }
+    if args.split_parents {
+        let (adjacent, distant) = summary.split_reads.split_junctions(args.parent_window); 
+        header.push_str("\tSplit_parents\tSplit_children\tSplit_adjacent\tSplit_distant\tChild_fold_reads\tChild_chim_reads"); 
+        all_stats.extend([summary.split_reads.num_parents(), summary.split_reads.children.reads_pass_filter, adjacent, distant,
+            summary.split_reads.children.read_counts[0], summary.split_reads.children.read_counts[1]].iter().map(|n| n.to_string())); 
+    }
+    if args.gtf.is_some() {
+        header.push_str("\tChim_gene_pairs"); 
+        all_stats.push(summary.gene_pairs.values().map(|c| c[0]).sum::<u64>().to_string()); 
+    }
+    if args.group_by.is_some() {
+        header.push_str("\tgroup"); 
+        all_stats.push(String::from("all")); 
+        for (row, name) in group_rows.iter_mut().zip(summary.tag_groups.keys()) {
+            row.resize(all_stats.len() - 1, String::from("NA")); 
+            row.push(name.clone()); 
+        }
+    }
+    //read_policy follows sample so that the sample column keeps its place for tables of earlier runs
+    header.push_str("\tsample\tread_policy"); 
+    all_stats.push(args.input().to_string()); 
+    all_stats.push(policy_name(args)); 
+    writeln!(handle,"{}", header)?; 
     writeln!(handle, "{}", all_stats.join("\t"))?; 
+    for mut row in group_rows {
+        row.push(args.input().to_string()); 
+        row.push(policy_name(args)); 
+        writeln!(handle, "{}", row.join("\t"))?; 
+    }
     Ok(())
 }
 
@@ -155,3 +984,79 @@ fn get_percent(a:u64, b:u64) -> String {
     let p = 100.0 * a as f32 / b as f32; 
     return format!("{:.3}", p); 
 }
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use super::{Breakpoint, Cli, Resources, get_label, policy_class, read_pattern};
+
+    fn args(opts: &[&str]) -> Cli {
+        Cli::parse_from(["breakinator", "-i", "reads.paf", "--paf"].iter().chain(opts))
+    }
+
+    fn brk(directions: &str, chr2: &str, loc2: i64, label: &str) -> Breakpoint {
+        Breakpoint{b1_chr: String::from("chr1"), b1_loc: 1000, directions: directions.to_string(), b2_chr: chr2.to_string(), b2_loc: loc2,
+            label: Some(label.to_string()), ..Default::default()}
+    }
+
+    #[test]
+    fn inverted_repeat_labels() {
+        let args_no_sym = args(&["--inverted-repeats", "--no-sym"]);
+        let args = args(&["--inverted-repeats"]);
+        let res = Resources::load(&args);
+        let fold = |r2_loc: u32, ir_arm: Option<u32>| Breakpoint{read_len: 1000, r1_loc: r2_loc, r2_loc, ir_arm, ..brk("><", "chr1", 1050, "Foldback")};
+        assert_eq!(get_label(&fold(520, Some(20)), &args, &res), "InvertedRepeat");
+        assert_eq!(get_label(&fold(520, Some(10)), &args, &res), "Foldback");
+        //folds failing the symmetry test pass whatever the reference around them
+        assert_eq!(get_label(&fold(625, Some(20)), &args, &res), "Pass");
+        assert_eq!(get_label(&fold(625, Some(20)), &args_no_sym, &res), "InvertedRepeat");
+    }
+
+    #[test]
+    fn read_patterns() {
+        let args = args(&[]);
+        let fold = brk("><", "chr1", 1050, "Foldback");
+        let chim = brk(">>", "chr2", 5000, "Chimeric");
+        let pass = brk(">>", "chr1", 9000, "Pass");
+        assert_eq!(read_pattern(std::slice::from_ref(&fold), &args), None);
+        //fold shaped junctions make a concatemer whatever their label
+        assert_eq!(read_pattern(&[fold.clone(), brk("<>", "chr1", 1000, "Pass")], &args), Some(0));
+        assert_eq!(read_pattern(&[chim.clone(), chim.clone()], &args), Some(1));
+        assert_eq!(read_pattern(&[fold.clone(), pass.clone(), chim.clone()], &args), Some(2));
+        assert_eq!(read_pattern(&[pass.clone(), chim.clone()], &args), None);
+        //more than --max-segments segments
+        assert_eq!(read_pattern(&vec![pass.clone(); 5], &args), Some(3));
+        assert_eq!(read_pattern(&vec![pass; 4], &args), None);
+    }
+
+    #[test]
+    fn majority_policy() {
+        let args = args(&[]);
+        assert_eq!(policy_class(&["Foldback", "Chimeric"], &args), 0);
+        assert_eq!(policy_class(&["Chimeric", "Foldback", "Chimeric"], &args), 1);
+        assert_eq!(policy_class(&["Pass", "Masked"], &args), 2);
+        assert_eq!(policy_class(&["Pass", "Palindromic"], &args), 4);
+        assert_eq!(policy_class(&["Palindromic", "Chimeric"], &args), 1);
+    }
+
+    #[test]
+    fn read_policies() {
+        let first = args(&["--read-policy", "first-breakpoint"]);
+        assert_eq!(policy_class(&["Chimeric", "Foldback", "Foldback"], &first), 1);
+        assert_eq!(policy_class(&["Pass", "Foldback"], &first), 2);
+        assert_eq!(policy_class(&["Palindromic", "Pass"], &first), 4);
+
+        let any = args(&["--read-policy", "any-chimera"]);
+        assert_eq!(policy_class(&["Foldback", "Foldback", "Chimeric"], &any), 1);
+        assert_eq!(policy_class(&["Foldback", "Pass"], &any), 0);
+
+        let frac = args(&["--read-policy", "min-fraction", "--min-artifact-frac", "0.5"]);
+        assert_eq!(policy_class(&["Foldback", "Pass", "Pass"], &frac), 2);
+        assert_eq!(policy_class(&["Foldback", "Pass"], &frac), 0);
+        assert_eq!(policy_class(&["Chimeric", "Chimeric", "Foldback"], &frac), 1);
+
+        let mixed = args(&["--read-policy", "mixed"]);
+        assert_eq!(policy_class(&["Foldback", "Chimeric"], &mixed), 3);
+        assert_eq!(policy_class(&["Foldback", "Pass"], &mixed), 0);
+    }
+}
@@ -1,22 +1,59 @@
 use clap::Parser;
-use breakinator::{Cli, paf, sam};
+use breakinator::{Cli, paf, sam, vcf};
+use breakinator::cli::Command;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     //read in args 
     let args = Cli::parse();
+
+    if let Some(Command::AnnotateVcf(vcf_args)) = &args.command {
+        vcf::annotate_vcf(vcf_args)?;
+        return Ok(());
+    }
+
+    if (args.seq_context || args.inverted_repeats || args.gaps || args.telomere_motif.is_some()) && args.genome.is_none() {
+        eprintln!("ERROR: --genome <FASTA> is required with --seq-context, --inverted-repeats, --gaps and --telomere-motif.");
+        std::process::exit(1);
+    }
+    
+    if args.bin_minutes < 1 {
+        eprintln!("ERROR: --bin-minutes must be at least 1.");
+        std::process::exit(1);
+    }
+
+    if !(0.0..=1.0).contains(&args.min_artifact_frac) {
+        eprintln!("ERROR: --min-artifact-frac must be between 0 and 1.");
+        std::process::exit(1);
+    }
+
+    if args.paf && !args.adapters.is_empty() {
+        eprintln!("ERROR: --adapters needs the read sequence of a SAM/BAM/CRAM file.");
+        std::process::exit(1);
+    }
+
+    //adapters with half or more of their bases allowed to mismatch would match almost any sequence between segments
+    if let Some(spec) = args.adapters.iter().find(|spec| spec.split_once('=').map_or(spec.as_str(), |(_, seq)| seq).len() as u32 <= 2 * args.adapter_mismatches) {
+        eprintln!("ERROR: --adapters sequence {} must be longer than twice --adapter-mismatches ({}).", spec, args.adapter_mismatches);
+        std::process::exit(1);
+    }
+
+    if args.paf && args.group_by.as_ref().is_some_and(|tag| tag.contains(':')) {
+        eprintln!("ERROR: --group-by RG:FIELD needs the @RG header of a SAM/BAM/CRAM file.");
+        std::process::exit(1);
+    }
     
     if args.paf { 
-        if !args.input.to_lowercase().ends_with(".paf") {
+        if !args.input().to_lowercase().ends_with(".paf") {
             eprintln!("ERROR: File name does not end in paf-ensure file is paf");
         }   
         paf::process_paf(&args)?;
     } else {
-        if args.input.to_lowercase().ends_with(".paf") {
+        if args.input().to_lowercase().ends_with(".paf") {
             eprintln!("ERROR: include --paf argument if running on paf file");
             std::process::exit(1);
         }   
         
-        let is_cram = args.input.to_lowercase().ends_with(".cram");
+        let is_cram = args.input().to_lowercase().ends_with(".cram");
         if is_cram && args.genome.is_none() {
             eprintln!("ERROR: --genome <FASTA> is required when reading CRAM files.");
             std::process::exit(1);
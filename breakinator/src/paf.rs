@@ -1,7 +1,11 @@
 use crate::cli::Cli;
 use std::{cmp::min, fs::File, io::{self, BufRead, BufReader, BufWriter, Write}, 
 iter::Peekable, process};
-use crate::{Breakpoint, classify_break, read_level_class, print_report, print_table};
+use crate::{Breakpoint, Resources, Summary, annotate_break, arm_overlap, classify_break, read_level_class, print_report, print_table, tsv_header};
+use crate::junction::JunctionIndex;
+use crate::panel::{add_junction, update_panel};
+use crate::assembly::AssemblyQc;
+use crate::parents::Segment;
 
 
 pub fn process_paf(args: &Cli) ->  Result<(), Box<dyn std::error::Error>>  {
@@ -11,23 +15,22 @@ pub fn process_paf(args: &Cli) ->  Result<(), Box<dyn std::error::Error>>  {
         process::exit(1);
     }
 
-    let file = File::open(&args.input)?;
+    let file = File::open(args.input())?;
     let mut reader = BufReader::new(file).lines().peekable();
 
     let output = File::create(&args.out)?;
     let mut writer = BufWriter::new(output);
-    if args.rcoord {
-        writeln!(writer, "#Break1_chr\tBreak1_loc\tBreak_direction\tBreak2_chr\tBreak2_loc\tMapQ\tRead_ID\tClassification\tbreak1_read\tbreak2_read")?;
-    } else { 
-        writeln!(writer, "#Break1_chr\tBreak1_loc\tBreak_direction\tBreak2_chr\tBreak2_loc\tMapQ\tRead_ID\tClassification")?;
-    }
+    writeln!(writer, "{}", tsv_header(args))?;
     // instantiate empty cluster
     let mut cluster = Vec::with_capacity(5);
 
+    //files used to classify breakpoints and junctions of this sample to add to a panel of normals
+    let res = Resources::load(args); 
+    let mut sample_junctions: JunctionIndex<u64> = JunctionIndex::new(args.panel_window); 
+    let mut assembly_qc = AssemblyQc::new(); 
+
     //track read and break level classifications
-    let mut read_counts: Vec<u64> = vec![0; 3]; //[fold, chim, pass, unique] 
-    let mut break_counts: Vec<u64> = vec![0; 3]; //[fold, chim, pass] 
-    let mut reads_pass_filter: u64 = 0; 
+    let mut summary = Summary::new(&res); 
     
     while let Some(Ok(_)) = reader.peek() {
         //get all primary and supplementary alignments of a read
@@ -38,22 +41,70 @@ pub fn process_paf(args: &Cli) ->  Result<(), Box<dyn std::error::Error>>  {
         
         let num_pass = filtered.len(); 
         if num_pass > 0 {
-            reads_pass_filter += 1; 
+            summary.reads_pass_filter += 1; 
         } 
         
+        let mut all_breaks = Vec::new(); 
         if num_pass > 1 {
             //get all breaks in a read if there is more than one alignment 
-            let all_breaks = determine_break(&mut filtered, &args, &mut read_counts, &mut break_counts); 
-            for b in all_breaks {
-                writeln!(writer, "{}", b.as_tsv(&args.rcoord))?; 
+            all_breaks = determine_break(&mut filtered, &args, &res, &mut summary); 
+            for b in &all_breaks {
+                writeln!(writer, "{}", b.as_tsv(args))?; 
+                summary.add_break(b, &res); 
+                if args.panel_add.is_some() {
+                    add_junction(&mut sample_junctions, b); 
+                }
+                if args.assembly_qc.is_some() {
+                    assembly_qc.add(b, args); 
+                }
+            }
+            if args.read_patterns {
+                summary.add_read_pattern(&all_breaks, args); 
             }
             } 
+        if num_pass > 0 {
+            if let Some(targets) = &res.targets {
+                //on-target when any segment overlaps a target region
+                let on_target = filtered.iter().any(|f| targets.overlaps(f[5], f[7].parse::<i64>().unwrap_or(0), f[8].parse::<i64>().unwrap_or(0))); 
+                summary.target_counts[!on_target as usize].add_read(&all_breaks, args); 
+            }
+            if let Some(tag) = &args.group_by {
+                summary.tag_groups.entry(read_group(&cluster, tag)).or_default().add_read(&all_breaks, args); 
+            }
+            if args.split_parents {
+                let parent = read_group(&cluster, "pi"); 
+                if parent != "NA" {
+                    //children are ordered by their split position in the parent
+                    let split_pos = read_group(&cluster, "sp").parse::<i64>().unwrap_or(0); 
+                    summary.split_reads.add_child(&parent, split_pos, segment(&filtered[0]), segment(&filtered[num_pass - 1]), &all_breaks, args); 
+                }
+            }
+            let info = res.seq_summary.as_ref().and_then(|s| s.get(filtered[0][0])); 
+            if res.seq_summary.is_some() {
+                let end_reason = info.map_or(String::from("NA"), |i| i.end_reason.clone()); 
+                summary.end_reasons.entry(end_reason).or_default().add_read(&all_breaks, args); 
+            }
+            if args.time_bins.is_some() {
+                summary.time_bins.add_read(&read_group(&cluster, "st"), info, &all_breaks, args); 
+            }
         }
+        }
+    if let Some(path) = &args.panel_add {
+        update_panel(path, &sample_junctions, args)?; 
+    }
+
+    if let Some(path) = &args.assembly_qc {
+        assembly_qc.write(path, args)?; 
+    }
+
+    if let Some(path) = &args.time_bins {
+        summary.time_bins.write(path, args)?; 
+    }
     //write results as tsv or print summary  to terminal 
     if args.tabular {
-        print_table(reads_pass_filter, &read_counts, &break_counts, args.input.to_string()).expect("error writing to stdout")
+        print_table(&summary, args).expect("error writing to stdout")
     }else {
-        print_report(reads_pass_filter, &read_counts, &break_counts,  &args).expect("error writing to stdout"); 
+        print_report(&summary, &args).expect("error writing to stdout"); 
     }
     Ok(())
 }
@@ -80,6 +131,22 @@ where
     Ok(())
 }
 
+//reference placement of a PAF line
+fn segment(fields: &[&str]) -> Segment {
+    Segment{chr: fields[5].to_string(), start: fields[7].parse::<i64>().expect("invalid int in field 8"),
+        end: fields[8].parse::<i64>().expect("invalid int in field 9"), reverse: fields[4] == "-"}
+}
+
+//value of a SAM-style tag (TAG:TYPE:VALUE) of the primary alignment of the read, NA when missing
+fn read_group(cluster: &[String], tag: &str) -> String {
+    let primary = cluster.iter().find(|line| line.split('\t').skip(12).any(|f| f == "tp:A:P")).unwrap_or(&cluster[0]); 
+    let prefix = format!("{}:", tag); 
+    match primary.split('\t').skip(12).find(|f| f.starts_with(&prefix)) {
+        Some(field) => field.splitn(3, ':').nth(2).unwrap_or("NA").to_string(),
+        None => String::from("NA"),
+    }
+}
+
 //filter out read alignments that fail length or mapQ filters or is secondary alignment 
 fn filter_alignments(all_maps: &Vec<String>, mapq: u8, map_len: u32) -> Vec<Vec<&str>>{
     let mut passed_filter: Vec<Vec<&str>> =  Vec::new(); 
@@ -93,11 +160,10 @@ fn filter_alignments(all_maps: &Vec<String>, mapq: u8, map_len: u32) -> Vec<Vec<
 }
 
 //find the breakpoints and a read that has at least one supplementary alignment
-fn determine_break(clust: &mut Vec<Vec<&str>>, args: &Cli, read_counts: &mut Vec<u64>, break_counts: &mut Vec<u64>) ->  Vec<Breakpoint> {
+fn determine_break(clust: &mut Vec<Vec<&str>>, args: &Cli, res: &Resources, summary: &mut Summary) ->  Vec<Breakpoint> {
     //sort by start location of aligment in read 
     clust.sort_by_key(|line| {line[2].parse::<i32>().expect("invalid int in field 3")});
     let mut out: Vec<Breakpoint> = Vec::new(); 
-    let mut labels: Vec<u32> = vec![0; 3];  //[fold, chim, pass count]
 
     //check every concurrent alignment in a read with n split alignments 
     for i in 0..(clust.len() - 1) {
@@ -143,9 +209,17 @@ fn determine_break(clust: &mut Vec<Vec<&str>>, args: &Cli, read_counts: &mut Vec
 
         let mut break_info = Breakpoint{b1_chr: b1[0].to_string() ,b1_loc: b1[1].parse::<i64>().expect("Failed to parse b1_loc as i64"), directions: directions,
             b2_chr: b2[0].to_string(), b2_loc: b2[1].parse::<i64>().expect("Failed to parse b2_loc as i64"),
+            b1_chr_len: clust[i][6].parse::<i64>().expect("invalid int in field 7"), b2_chr_len: clust[i+1][6].parse::<i64>().expect("invalid int in field 7"),
+            read_info: res.seq_summary.as_ref().and_then(|s| s.get(clust[0][0])).cloned(),
             mapq: mapq, read_id: clust[0][0].to_string(), read_len: read_length, label: None, 
             r1_loc: clust[i][3].parse::<u32>().expect("Failed to parse r1_loc as u32"),
-            r2_loc: clust[i+1][2].parse::<u32>().expect("Failed to parse r2_loc as u32")};  
+            r2_loc: clust[i+1][2].parse::<u32>().expect("Failed to parse r2_loc as u32"),
+            b1_ref: (clust[i][7].parse::<i64>().expect("invalid int in field 8"), clust[i][8].parse::<i64>().expect("invalid int in field 9")),
+            b2_ref: (clust[i+1][7].parse::<i64>().expect("invalid int in field 8"), clust[i+1][8].parse::<i64>().expect("invalid int in field 9")),
+            b1_arm_len: clust[i][3].parse::<u32>().expect("invalid int in field 4") - clust[i][2].parse::<u32>().expect("invalid int in field 3"),
+            b2_arm_len: clust[i+1][3].parse::<u32>().expect("invalid int in field 4") - clust[i+1][2].parse::<u32>().expect("invalid int in field 3"),
+            ..Default::default()};  
+        break_info.arm_overlap = arm_overlap(&break_info); 
        
         
         //let rlen = clust[i][1].parse::<f32>().expect("invalid int in field 2"); 
@@ -153,12 +227,8 @@ fn determine_break(clust: &mut Vec<Vec<&str>>, args: &Cli, read_counts: &mut Vec
 
         //get artifact or pass classification 
         //let label = classify_break( &break_info, args, rlen, rbreak, break_counts); 
-        let label = classify_break( &break_info, args, break_counts);  
-        match label.as_str() {
-            "Foldback" => labels[0] += 1,
-            "Chimeric" => labels[1] += 1,
-            _ => labels[2] += 1,
-        }
+        annotate_break(&mut break_info, args, res); 
+        let label = classify_break( &break_info, args, res, &mut summary.break_counts);  
         //break_info.push(label); 
         break_info.label = Some(label); 
     
@@ -166,7 +236,7 @@ fn determine_break(clust: &mut Vec<Vec<&str>>, args: &Cli, read_counts: &mut Vec
     
     }
     //classify the read based on the classification of all breakpoints on the read
-    read_level_class(&labels, read_counts); 
+    read_level_class(&out, args, &mut summary.read_counts); 
     return out; 
     
 } 
@@ -0,0 +1,104 @@
+use std::{collections::HashSet, fs::File, io::{self, BufRead, BufReader, BufWriter, Write}, path::Path};
+use crate::junction::{Junction, JunctionIndex};
+use crate::{Breakpoint, Cli};
+
+// sample and read support of one recurrent junction in the panel of normals
+pub struct PanelSite {
+    pub samples: u32,
+    pub reads: u64,
+}
+
+// persistent panel of artifact junctions accumulated over many breakinator runs
+pub struct Panel {
+    sites: JunctionIndex<PanelSite>,
+}
+
+impl Panel {
+    pub fn new(window: i64) -> Panel {
+        Panel{sites: JunctionIndex::new(window)}
+    }
+
+    //read a panel TSV
+    pub fn load(path: &str, window: i64) -> io::Result<Panel> {
+        let mut sites = JunctionIndex::new(window);
+        let reader = BufReader::new(File::open(path)?);
+        for line in reader.lines() {
+            let line = line?;
+            if line.starts_with('#') || line.trim().is_empty() {
+                continue;
+            }
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() < 7 {
+                eprintln!("Warning: skipping malformed panel line: {}", line);
+                continue;
+            }
+            let j = Junction::new(fields[0], fields[1].parse::<i64>().expect("invalid panel position"), fields[2],
+                fields[3], fields[4].parse::<i64>().expect("invalid panel position"));
+            let site = PanelSite{samples: fields[5].parse::<u32>().expect("invalid panel sample count"),
+                reads: fields[6].parse::<u64>().expect("invalid panel read count")};
+            sites.insert(j, site);
+        }
+        Ok(Panel{sites})
+    }
+
+    //breakpoint matches a site seen in at least min_samples samples of the panel
+    pub fn is_systematic(&self, brk: &Breakpoint, min_samples: u32) -> bool {
+        match self.sites.get(&Junction::from_break(brk)) {
+            Some(site) => site.samples >= min_samples,
+            None => false,
+        }
+    }
+
+    //add the junctions of one sample, each site is counted once per sample
+    pub fn add_sample(&mut self, sample: &JunctionIndex<u64>) {
+        //sites already matched by a junction of this sample, several junctions can fall in the window of one site
+        let mut seen: HashSet<Junction> = HashSet::new();
+        for (j, reads) in sample.iter() {
+            match self.sites.get_entry_mut(j) {
+                Some((site_j, site)) => {
+                    if seen.insert(site_j.clone()) {
+                        site.samples += 1;
+                    }
+                    site.reads += reads;
+                }
+                None => {
+                    seen.insert(j.clone());
+                    self.sites.insert(j.clone(), PanelSite{samples: 1, reads: *reads});
+                }
+            }
+        }
+    }
+
+    pub fn write(&self, path: &str) -> io::Result<()> {
+        let mut all: Vec<&(Junction, PanelSite)> = self.sites.iter().collect();
+        all.sort_by(|a, b| (&a.0.chr1, a.0.pos1, &a.0.chr2, a.0.pos2).cmp(&(&b.0.chr1, b.0.pos1, &b.0.chr2, b.0.pos2)));
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "#Chr1\tPos1\tDirection\tChr2\tPos2\tNum_samples\tNum_reads")?;
+        for (j, site) in all {
+            writeln!(writer, "{}\t{}\t{}\t{}\t{}\t{}\t{}", j.chr1, j.pos1, j.directions, j.chr2, j.pos2, site.samples, site.reads)?;
+        }
+        Ok(())
+    }
+}
+
+//collect an artifact breakpoint of the current sample for --panel-add
+pub fn add_junction(sample: &mut JunctionIndex<u64>, brk: &Breakpoint) {
+    match brk.label.as_deref() {
+        Some("Foldback") | Some("Chimeric") | Some("SystematicArtifact") => {
+            let j = Junction::from_break(brk);
+            match sample.get_mut(&j) {
+                Some(reads) => *reads += 1,
+                None => sample.insert(j, 1),
+            }
+        }
+        _ => {}
+    }
+}
+
+//merge the junctions of the current sample into the panel at --panel-add
+//the panel file is created when it does not exist yet
+pub fn update_panel(path: &str, sample: &JunctionIndex<u64>, args: &Cli) -> io::Result<()> {
+    let mut panel = if Path::new(path).exists() { Panel::load(path, args.panel_window)? } else { Panel::new(args.panel_window) };
+    panel.add_sample(sample);
+    panel.write(path)
+}
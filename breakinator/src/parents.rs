@@ -0,0 +1,79 @@
+use std::collections::{BTreeMap, HashMap};
+use crate::{Breakpoint, Cli, Counts};
+
+// reference placement of an aligned segment
+pub struct Segment {
+    pub chr: String,
+    pub start: i64,
+    pub end: i64,
+    pub reverse: bool,
+}
+
+impl Segment {
+    //reference position where the read leaves the segment
+    fn exit(&self) -> i64 {
+        if self.reverse { self.start } else { self.end }
+    }
+
+    //reference position where the read enters the segment
+    fn entry(&self) -> i64 {
+        if self.reverse { self.end } else { self.start }
+    }
+}
+
+// first and last aligned segment of a child read in read order
+struct Child {
+    split_pos: i64,
+    first: Segment,
+    last: Segment,
+}
+
+// reads split by the basecaller, linked to their parent read by the pi:Z tag
+#[derive(Default)]
+pub struct SplitReads {
+    parents: HashMap<String, Vec<Child>>,
+    pub children: Counts,
+}
+
+impl SplitReads {
+    pub fn new() -> SplitReads {
+        SplitReads::default()
+    }
+
+    //add a child read with its split position in the parent (sp:i tag) and its breakpoints
+    pub fn add_child(&mut self, parent: &str, split_pos: i64, first: Segment, last: Segment, breaks: &[Breakpoint], args: &Cli) {
+        self.parents.entry(parent.to_string()).or_default().push(Child{split_pos, first, last});
+        self.children.add_read(breaks, args);
+    }
+
+    pub fn num_parents(&self) -> u64 {
+        self.parents.len() as u64
+    }
+
+    //number of parents by the number of children they were split into
+    pub fn children_per_parent(&self) -> BTreeMap<usize, u64> {
+        let mut out = BTreeMap::new();
+        for children in self.parents.values() {
+            *out.entry(children.len()).or_insert(0) += 1;
+        }
+        out
+    }
+
+    //splits between consecutive children that map adjacently (same contig and strand within window) and that do not
+    pub fn split_junctions(&self, window: i64) -> (u64, u64) {
+        let (mut adjacent, mut distant) = (0, 0);
+        for children in self.parents.values() {
+            let mut ordered: Vec<&Child> = children.iter().collect();
+            ordered.sort_by_key(|c| c.split_pos);
+            for pair in ordered.windows(2) {
+                let (a, b) = (&pair[0].last, &pair[1].first);
+                if a.chr == b.chr && a.reverse == b.reverse && (a.exit() - b.entry()).abs() <= window {
+                    adjacent += 1;
+                } else {
+                    distant += 1;
+                }
+            }
+        }
+        (adjacent, distant)
+    }
+}
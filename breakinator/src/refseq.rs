@@ -0,0 +1,330 @@
+use std::{collections::HashMap, ffi::CString, fs::File, io::{self, BufRead, BufReader}, os::raw::c_void};
+use rust_htslib::{bgzf, htslib};
+use crate::Breakpoint;
+
+// indexed reference FASTA given with --genome
+pub struct Genome {
+    //htslib index used directly, faidx::Reader::fetch_seq never frees the sequences it fetches
+    fai: *mut htslib::faidx_t,
+    lens: HashMap<String, i64>,
+}
+
+impl Genome {
+    pub fn load(path: &str) -> io::Result<Genome> {
+        //htslib builds the .fai index if it is missing
+        let cpath = CString::new(path).map_err(io::Error::other)?;
+        let fai = unsafe { htslib::fai_load(cpath.as_ptr()) };
+        if fai.is_null() {
+            return Err(io::Error::other(format!("could not load or index {}", path)));
+        }
+        let mut lens = HashMap::new();
+        for line in BufReader::new(File::open(format!("{}.fai", path))?).lines() {
+            let line = line?;
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() >= 2 {
+                lens.insert(fields[0].to_string(), fields[1].parse::<i64>().expect("invalid length in .fai"));
+            }
+        }
+        Ok(Genome{fai, lens})
+    }
+
+    pub fn contig_len(&self, chr: &str) -> Option<i64> {
+        self.lens.get(chr).copied()
+    }
+
+    //uppercase reference sequence of [start, end), clipped to the contig
+    pub fn fetch(&self, chr: &str, start: i64, end: i64) -> Vec<u8> {
+        let len = match self.contig_len(chr) {
+            Some(l) => l,
+            None => return Vec::new(),
+        };
+        let (start, end) = (start.max(0), end.min(len));
+        if start >= end {
+            return Vec::new();
+        }
+        let cname = match CString::new(chr) {
+            Ok(c) => c,
+            Err(_) => return Vec::new(),
+        };
+        let mut fetched: htslib::hts_pos_t = 0;
+        unsafe {
+            let ptr = htslib::faidx_fetch_seq64(self.fai, cname.as_ptr(), start, end - 1, &mut fetched);
+            if ptr.is_null() {
+                return Vec::new();
+            }
+            let seq = std::slice::from_raw_parts(ptr as *const u8, fetched.max(0) as usize).to_ascii_uppercase();
+            //the sequence is allocated by htslib for each fetch
+            htslib::hts_free(ptr as *mut c_void);
+            seq
+        }
+    }
+}
+
+impl Drop for Genome {
+    fn drop(&mut self) {
+        unsafe { htslib::fai_destroy(self.fai) };
+    }
+}
+
+// runs of N in the reference assembly, sorted by start for each contig
+pub struct Gaps {
+    contigs: HashMap<String, Vec<(i64, i64)>>,
+}
+
+impl Gaps {
+    //scan the FASTA (plain or bgzipped) for N runs of at least min_gap bps
+    pub fn load(path: &str, min_gap: i64) -> io::Result<Gaps> {
+        let reader = bgzf::Reader::from_path(path).map_err(io::Error::other)?;
+        let mut contigs: HashMap<String, Vec<(i64, i64)>> = HashMap::new();
+        let mut chr = String::new();
+        let mut pos: i64 = 0;
+        let mut run_start: Option<i64> = None;
+        for line in BufReader::new(reader).lines() {
+            let line = line?;
+            if let Some(name) = line.strip_prefix('>') {
+                close_gap(&mut contigs, &chr, &mut run_start, pos, min_gap);
+                chr = name.split_whitespace().next().unwrap_or("").to_string();
+                pos = 0;
+                continue;
+            }
+            for b in line.trim_end().bytes() {
+                if b == b'N' || b == b'n' {
+                    run_start.get_or_insert(pos);
+                } else {
+                    close_gap(&mut contigs, &chr, &mut run_start, pos, min_gap);
+                }
+                pos += 1;
+            }
+        }
+        close_gap(&mut contigs, &chr, &mut run_start, pos, min_gap);
+        Ok(Gaps{contigs})
+    }
+
+    //same strand breakpoint whose ends lie on either side of the same gap, each within tol of its edge
+    pub fn spans(&self, brk: &Breakpoint, tol: i64) -> bool {
+        if brk.b1_chr != brk.b2_chr || !(brk.directions == ">>" || brk.directions == "<<") {
+            return false;
+        }
+        let gaps = match self.contigs.get(&brk.b1_chr) {
+            Some(g) => g,
+            None => return false,
+        };
+        let (lo, hi) = (brk.b1_loc.min(brk.b2_loc), brk.b1_loc.max(brk.b2_loc));
+        let first = gaps.partition_point(|g| g.0 < lo);
+        gaps[first..].iter().take_while(|g| g.0 <= lo + tol).any(|g| hi >= g.1 && hi - g.1 <= tol)
+    }
+}
+
+fn close_gap(contigs: &mut HashMap<String, Vec<(i64, i64)>>, chr: &str, run_start: &mut Option<i64>, pos: i64, min_gap: i64) {
+    if let Some(start) = run_start.take() {
+        if pos - start >= min_gap {
+            contigs.entry(chr.to_string()).or_default().push((start, pos));
+        }
+    }
+}
+
+pub fn revcomp(seq: &[u8]) -> Vec<u8> {
+    seq.iter().rev().map(|b| match b {
+        b'A' => b'T',
+        b'C' => b'G',
+        b'G' => b'C',
+        b'T' => b'A',
+        _ => b'N',
+    }).collect()
+}
+
+fn gc_fraction(seq: &[u8]) -> Option<f32> {
+    let acgt = seq.iter().filter(|b| matches!(b, b'A' | b'C' | b'G' | b'T')).count();
+    if acgt == 0 {
+        return None;
+    }
+    let gc = seq.iter().filter(|b| matches!(b, b'G' | b'C')).count();
+    Some(gc as f32 / acgt as f32)
+}
+
+//reference before and after a breakpoint end in read orientation
+fn read_flanks(genome: &Genome, chr: &str, loc: i64, dir: char, flank: i64) -> (Vec<u8>, Vec<u8>) {
+    let left = genome.fetch(chr, loc - flank, loc);
+    let right = genome.fetch(chr, loc, loc + flank);
+    if dir == '<' {
+        (revcomp(&right), revcomp(&left))
+    } else {
+        (left, right)
+    }
+}
+
+//add microhomology length and flank GC content of the junction to the breakpoint
+pub fn add_seq_context(brk: &mut Breakpoint, genome: &Genome, flank: i64) {
+    let dirs: Vec<char> = brk.directions.chars().collect();
+    //last bases of the first segment and the reference it would continue with
+    let (last1, cont1) = read_flanks(genome, &brk.b1_chr, brk.b1_loc, dirs[0], flank);
+    //reference preceding the second segment and its first bases
+    let (prev2, first2) = read_flanks(genome, &brk.b2_chr, brk.b2_loc, dirs[1], flank);
+    if (last1.is_empty() && cont1.is_empty()) || (prev2.is_empty() && first2.is_empty()) {
+        return;
+    }
+
+    //bases at the junction that could be aligned to either segment
+    let forward = cont1.iter().zip(first2.iter()).take_while(|(a, b)| a == b && **a != b'N').count();
+    let backward = last1.iter().rev().zip(prev2.iter().rev()).take_while(|(a, b)| a == b && **a != b'N').count();
+    brk.mh_len = Some((forward + backward) as u32);
+
+    brk.b1_gc = gc_fraction(&[last1, cont1].concat());
+    brk.b2_gc = gc_fraction(&[prev2, first2].concat());
+}
+
+fn complement(b: u8) -> u8 {
+    match b {
+        b'A' => b'T',
+        b'C' => b'G',
+        b'G' => b'C',
+        b'T' => b'A',
+        _ => b'N',
+    }
+}
+
+//longest reference inverted repeat centred within window of a foldback, as (arm length, spacer length)
+pub fn find_inverted_repeat(genome: &Genome, chr: &str, center: i64, window: i64, max_spacer: i64) -> Option<(u32, u32)> {
+    //arms can extend up to another window beyond the centres searched
+    let start = center - 2 * window - max_spacer; 
+    let seq = genome.fetch(chr, start, center + 2 * window + max_spacer + 1);
+    if seq.is_empty() {
+        return None;
+    }
+    //position of the fold in seq, the fetch is clipped at the start of the contig
+    let offset = center - start.max(0);
+    let n = seq.len() as i64;
+
+    let mut best: Option<(u32, u32)> = None;
+    for mid in (offset - window).max(0)..(offset + window).min(n) {
+        for spacer in 0..=max_spacer {
+            //left arm ends at i and right arm starts at j with the spacer in between
+            let i = mid - spacer / 2 - 1;
+            let j = i + 1 + spacer;
+            let mut arm = 0;
+            while i - arm >= 0 && j + arm < n {
+                let a = seq[(i - arm) as usize];
+                if a == b'N' || a != complement(seq[(j + arm) as usize]) {
+                    break;
+                }
+                arm += 1;
+            }
+            if arm > 0 && best.is_none_or(|(b_arm, b_spacer)| arm as u32 > b_arm || (arm as u32 == b_arm && (spacer as u32) < b_spacer)) {
+                best = Some((arm as u32, spacer as u32));
+            }
+        }
+    }
+    best
+}
+
+//bps on either side of a breakpoint end scanned for telomeric repeats
+const TELOMERE_WINDOW: i64 = 100;
+
+//at least half of the reference around the position is covered by the motif on either strand
+pub fn is_telomeric(genome: &Genome, chr: &str, loc: i64, motif: &[u8]) -> bool {
+    let seq = genome.fetch(chr, loc - TELOMERE_WINDOW, loc + TELOMERE_WINDOW);
+    if motif.is_empty() || seq.len() < motif.len() {
+        return false;
+    }
+    let rc = revcomp(motif);
+    let mut covered = vec![false; seq.len()];
+    for i in 0..=(seq.len() - motif.len()) {
+        let kmer = &seq[i..i + motif.len()];
+        if kmer == motif || kmer == rc.as_slice() {
+            covered[i..i + motif.len()].iter_mut().for_each(|c| *c = true);
+        }
+    }
+    2 * covered.iter().filter(|c| **c).count() >= seq.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, fs};
+    use super::{Gaps, Genome, add_seq_context, find_inverted_repeat, read_flanks, revcomp};
+    use crate::Breakpoint;
+
+    fn brk(loc1: i64, directions: &str, chr2: &str, loc2: i64) -> Breakpoint {
+        Breakpoint{b1_chr: String::from("chr1"), b1_loc: loc1, directions: directions.to_string(), b2_chr: chr2.to_string(), b2_loc: loc2, ..Default::default()}
+    }
+
+    #[test]
+    fn gap_spanning() {
+        let gaps = Gaps{contigs: HashMap::from([(String::from("chr1"), vec![(1000, 1100), (5000, 5500)])])};
+        assert!(gaps.spans(&brk(990, ">>", "chr1", 1110), 50));
+        assert!(gaps.spans(&brk(1110, "<<", "chr1", 990), 50));
+        assert!(gaps.spans(&brk(4960, ">>", "chr1", 5540), 50));
+        //each end must be within tol of the gap edge it flanks
+        assert!(!gaps.spans(&brk(900, ">>", "chr1", 1110), 50));
+        assert!(!gaps.spans(&brk(990, ">>", "chr1", 1200), 50));
+        //ends on either side of different gaps or inside a gap
+        assert!(!gaps.spans(&brk(990, ">>", "chr1", 5510), 50));
+        assert!(!gaps.spans(&brk(1010, ">>", "chr1", 1110), 50));
+        //only same strand junctions of one contig
+        assert!(!gaps.spans(&brk(990, "><", "chr1", 1110), 50));
+        assert!(!gaps.spans(&brk(990, ">>", "chr2", 1110), 50));
+    }
+
+    //pseudo random bases without long inverted repeats
+    fn filler(len: usize, seed: u64) -> Vec<u8> {
+        let mut x = seed;
+        (0..len).map(|_| {
+            x = x.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            b"ACGT"[(x >> 33) as usize % 4]
+        }).collect()
+    }
+
+    #[test]
+    fn inverted_repeats() {
+        //a 20 bp arm, 4 bp spacer and the reverse complement of the arm centred at 322
+        let arm = b"ACGGTCATTGCAGGATCCTA".to_vec();
+        let seq = [filler(300, 1), arm.clone(), b"AAAA".to_vec(), revcomp(&arm), filler(700, 2)].concat();
+        let path = std::env::temp_dir().join(format!("breakinator_ir_{}.fa", std::process::id()));
+        fs::write(&path, [b">chr1\n".to_vec(), seq, b"\n".to_vec()].concat()).unwrap();
+        let genome = Genome::load(path.to_str().unwrap()).unwrap();
+
+        let (found_arm, spacer) = find_inverted_repeat(&genome, "chr1", 322, 50, 10).unwrap();
+        assert!(found_arm >= 20);
+        assert_eq!(spacer, 4);
+        //the repeat is found anywhere within the window of the fold
+        assert!(find_inverted_repeat(&genome, "chr1", 360, 50, 10).unwrap().0 >= 20);
+        //away from the repeat only short chance matches remain
+        assert!(find_inverted_repeat(&genome, "chr1", 800, 50, 10).unwrap().0 < 12);
+        assert_eq!(find_inverted_repeat(&genome, "chr2", 322, 50, 10), None);
+
+        fs::remove_file(&path).ok();
+        fs::remove_file(format!("{}.fai", path.to_str().unwrap())).ok();
+    }
+
+    #[test]
+    fn seq_context() {
+        let path = std::env::temp_dir().join(format!("breakinator_flanks_{}.fa", std::process::id()));
+        fs::write(&path, ">chr1\nGGGGAACCTTGGGG\n>chr2\nTTTTACCAAAAA\n>chr3\nTTGGTAAAAA\n").unwrap();
+        let genome = Genome::load(path.to_str().unwrap()).unwrap();
+
+        //reference before and after the end in read orientation, reverse complemented on the reverse strand
+        assert_eq!(read_flanks(&genome, "chr1", 6, '>', 2), (b"AA".to_vec(), b"CC".to_vec()));
+        assert_eq!(read_flanks(&genome, "chr1", 6, '<', 2), (b"GG".to_vec(), b"TT".to_vec()));
+        //flanks are clipped at the contig ends
+        assert_eq!(read_flanks(&genome, "chr1", 1, '>', 3), (b"G".to_vec(), b"GGG".to_vec()));
+        assert_eq!(read_flanks(&genome, "chr1", 13, '<', 3), (b"C".to_vec(), b"CCC".to_vec()));
+
+        //CC continues both segments and A ends both
+        let mut same = brk(6, ">>", "chr2", 5);
+        add_seq_context(&mut same, &genome, 4);
+        assert_eq!(same.mh_len, Some(3));
+        assert_eq!(same.b1_gc, Some(0.5));
+        assert_eq!(same.b2_gc, Some(0.25));
+        //the same junction with the second segment on the reverse strand of chr3
+        let mut opposite = brk(6, "><", "chr3", 4);
+        add_seq_context(&mut opposite, &genome, 4);
+        assert_eq!(opposite.mh_len, Some(3));
+        assert_eq!(opposite.b2_gc, Some(0.25));
+        //no shared bases on the forward strand of chr3
+        let mut forward = brk(6, ">>", "chr3", 4);
+        add_seq_context(&mut forward, &genome, 4);
+        assert_eq!(forward.mh_len, Some(0));
+
+        fs::remove_file(&path).ok();
+        fs::remove_file(format!("{}.fai", path.to_str().unwrap())).ok();
+    }
+}
@@ -2,17 +2,22 @@ use rust_htslib::{
     bam::{self, Read, Record,},
     errors::Error as BamError,};
 use crate::cli::Cli;
-use std::{iter::Peekable};
-use rust_htslib::{bam::record::{Cigar}};
+use std::{collections::HashMap, iter::Peekable};
+use rust_htslib::{bam::record::{Aux, Cigar}};
 use rust_htslib::bam::ext::BamRecordExtensions;
 use std::cmp::min;
 use rust_htslib::bam::HeaderView;
 use std::{fs::File, io::{BufWriter, Write}};
-use crate::{Breakpoint, classify_break, read_level_class, print_report, print_table};
+use crate::{Breakpoint, Resources, Summary, annotate_break, arm_overlap, classify_break, read_level_class, print_report, print_table, tsv_header};
+use crate::junction::JunctionIndex;
+use crate::panel::{add_junction, update_panel};
+use crate::assembly::AssemblyQc;
+use crate::parents::Segment;
+use crate::refseq::revcomp;
 
 pub fn process_sam(args: &Cli, is_cram: bool) -> Result<(), Box<dyn std::error::Error>> { 
     // read in file 
-    let mut sam_reader = bam::Reader::from_path(&args.input).expect("Failed to open file");
+    let mut sam_reader = bam::Reader::from_path(args.input()).expect("Failed to open file");
     sam_reader.set_threads(args.threads)?;
     // If CRAM, attach reference
     if is_cram {
@@ -24,11 +29,7 @@ pub fn process_sam(args: &Cli, is_cram: bool) -> Result<(), Box<dyn std::error::
     //et mut out = Writer::from_stdout(&header, Format::Sam)?;
     let output = File::create(&args.out)?;
     let mut writer = BufWriter::new(output);
-    if args.rcoord {
-        writeln!(writer, "#Break1_chr\tBreak1_loc\tBreak_direction\tBreak2_chr\tBreak2_loc\tMapQ\tRead_ID\tClassification\tbreak1_read\tbreak2_read")?;
-    } else { 
-        writeln!(writer, "#Break1_chr\tBreak1_loc\tBreak_direction\tBreak2_chr\tBreak2_loc\tMapQ\tRead_ID\tClassification")?;
-    }
+    writeln!(writer, "{}", tsv_header(args))?;
  
     //peakable iterator of file 
     let mut sam_iter = sam_reader.records().peekable();
@@ -36,10 +37,17 @@ pub fn process_sam(args: &Cli, is_cram: bool) -> Result<(), Box<dyn std::error::
     //vector that store all alignments of one read (cluster of alignments)
     let mut cluster: Vec<Record> = Vec::with_capacity(5); 
 
+    //files used to classify breakpoints and junctions of this sample to add to a panel of normals
+    let res = Resources::load(args); 
+    let mut sample_junctions: JunctionIndex<u64> = JunctionIndex::new(args.panel_window); 
+    let mut assembly_qc = AssemblyQc::new(); 
+
     //track read and break level classifications
-    let mut read_counts: Vec<u64> = vec![0; 3]; //[fold, chim, pass, unique] 
-    let mut break_counts: Vec<u64> = vec![0; 3]; //[fold, chim, pass] 
-    let mut reads_pass_filter: u64 = 0; 
+    let mut summary = Summary::new(&res); 
+    let rg_fields = match args.group_by.as_ref().and_then(|tag| tag.strip_prefix("RG:")) {
+        Some(field) => read_group_fields(&header, field),
+        None => HashMap::new(),
+    };
     
     while sam_iter.peek().is_some() { 
         //move forward by one read group 
@@ -48,21 +56,73 @@ pub fn process_sam(args: &Cli, is_cram: bool) -> Result<(), Box<dyn std::error::
         
         let num_pass = filtered.len(); 
         if num_pass > 0 {
-            reads_pass_filter += 1; 
+            summary.reads_pass_filter += 1; 
         } 
+        let mut all_breaks = Vec::new(); 
         if num_pass > 1 {  
-            let all_breaks = determine_break(&mut filtered, &args, &mut read_counts, &mut break_counts, &header);
-            for b in all_breaks {
-                writeln!(writer, "{}", b.as_tsv(&args.rcoord))?; 
+            //the read sequence is only needed to search for adapters
+            let seq = if res.adapters.is_some() { read_sequence(&cluster) } else { Vec::new() }; 
+            all_breaks = determine_break(&mut filtered, &args, &res, &mut summary, &header, &seq);
+            for b in &all_breaks {
+                writeln!(writer, "{}", b.as_tsv(args))?; 
+                summary.add_break(b, &res); 
+                if args.panel_add.is_some() {
+                    add_junction(&mut sample_junctions, b); 
+                }
+                if args.assembly_qc.is_some() {
+                    assembly_qc.add(b, args); 
+                }
+            }
+            if args.read_patterns {
+                summary.add_read_pattern(&all_breaks, args); 
             }
             
         }
+        if num_pass > 0 {
+            if let Some(targets) = &res.targets {
+                //on-target when any segment overlaps a target region
+                let on_target = filtered.iter().any(|rec| rec.tid() >= 0 && targets.overlaps(
+                    std::str::from_utf8(header.tid2name(rec.tid() as u32)).unwrap(), rec.pos(), rec.reference_end())); 
+                summary.target_counts[!on_target as usize].add_read(&all_breaks, args); 
+            }
+            if let Some(tag) = &args.group_by {
+                summary.tag_groups.entry(read_group(&cluster, tag, &rg_fields)).or_default().add_read(&all_breaks, args); 
+            }
+            if args.split_parents {
+                let parent = read_group(&cluster, "pi", &rg_fields); 
+                if parent != "NA" {
+                    //children are ordered by their split position in the parent
+                    let split_pos = read_group(&cluster, "sp", &rg_fields).parse::<i64>().unwrap_or(0); 
+                    summary.split_reads.add_child(&parent, split_pos, segment(filtered[0], &header), segment(filtered[num_pass - 1], &header), &all_breaks, args); 
+                }
+            }
+            let info = res.seq_summary.as_ref().and_then(|s| s.get(std::str::from_utf8(cluster[0].qname()).unwrap())); 
+            if res.seq_summary.is_some() {
+                let end_reason = info.map_or(String::from("NA"), |i| i.end_reason.clone()); 
+                summary.end_reasons.entry(end_reason).or_default().add_read(&all_breaks, args); 
+            }
+            if args.time_bins.is_some() {
+                summary.time_bins.add_read(&read_group(&cluster, "st", &rg_fields), info, &all_breaks, args); 
+            }
+        }
+    }
+
+    if let Some(path) = &args.panel_add {
+        update_panel(path, &sample_junctions, args)?; 
+    }
+
+    if let Some(path) = &args.assembly_qc {
+        assembly_qc.write(path, args)?; 
+    }
+
+    if let Some(path) = &args.time_bins {
+        summary.time_bins.write(path, args)?; 
     }
 
     if args.tabular {
-        print_table(reads_pass_filter, &read_counts, &break_counts, args.input.to_string()).expect("error writing to stdout")
+        print_table(&summary, args).expect("error writing to stdout")
     }else {
-        print_report(reads_pass_filter, &read_counts, &break_counts,  &args).expect("error writing to stdout"); 
+        print_report(&summary, &args).expect("error writing to stdout"); 
     }
 
     Ok(())
@@ -91,6 +151,63 @@ where
     return Ok(()); 
 }
 
+//full read sequence in read orientation from the primary alignment, empty when it is not stored
+fn read_sequence(cluster: &[Record]) -> Vec<u8> {
+    match cluster.iter().find(|rec| !rec.is_secondary() && !rec.is_supplementary()) {
+        Some(rec) => {
+            let seq = rec.seq().as_bytes().to_ascii_uppercase(); 
+            if rec.is_reverse() { revcomp(&seq) } else { seq }
+        }
+        None => Vec::new(),
+    }
+}
+
+//reference placement of an alignment
+fn segment(rec: &Record, header: &HeaderView) -> Segment {
+    Segment{chr: std::str::from_utf8(header.tid2name(rec.tid() as u32)).unwrap().to_string(), start: rec.pos(), end: rec.reference_end(), reverse: rec.is_reverse()}
+}
+
+//value of the --group-by field of each @RG header line by read group ID
+fn read_group_fields(header: &HeaderView, field: &str) -> HashMap<String, String> {
+    let mut out = HashMap::new(); 
+    let prefix = format!("{}:", field); 
+    for line in String::from_utf8_lossy(header.as_bytes()).lines().filter(|l| l.starts_with("@RG")) {
+        let id = line.split('\t').find_map(|f| f.strip_prefix("ID:")); 
+        let value = line.split('\t').find_map(|f| f.strip_prefix(prefix.as_str())); 
+        if let (Some(id), Some(value)) = (id, value) {
+            out.insert(id.to_string(), value.to_string()); 
+        }
+    }
+    out
+}
+
+//--group-by value of the primary alignment of the read, NA when missing
+fn read_group(cluster: &[Record], tag: &str, rg_fields: &HashMap<String, String>) -> String {
+    let primary = cluster.iter().find(|rec| !rec.is_secondary() && !rec.is_supplementary()).unwrap_or(&cluster[0]); 
+    //RG:FIELD looks up the read group of the read in the header
+    let (tag, from_header) = match tag.split_once(':') {
+        Some((tag, _)) => (tag, true),
+        None => (tag, false),
+    }; 
+    let value = match primary.aux(tag.as_bytes()) {
+        Ok(Aux::String(s)) => s.to_string(),
+        Ok(Aux::Char(c)) => (c as char).to_string(),
+        Ok(Aux::I8(v)) => v.to_string(),
+        Ok(Aux::U8(v)) => v.to_string(),
+        Ok(Aux::I16(v)) => v.to_string(),
+        Ok(Aux::U16(v)) => v.to_string(),
+        Ok(Aux::I32(v)) => v.to_string(),
+        Ok(Aux::U32(v)) => v.to_string(),
+        Ok(Aux::Float(v)) => v.to_string(),
+        Ok(Aux::Double(v)) => v.to_string(),
+        _ => return String::from("NA"),
+    }; 
+    if from_header {
+        return rg_fields.get(&value).cloned().unwrap_or_else(|| String::from("NA")); 
+    }
+    value
+}
+
 //filter out read alignments that fail length or mapQ filters or is secondary alignment or unmapped
 fn filter_alignments<'a>(all_maps:&'a Vec<Record>, args: &Cli) ->  Vec<&'a Record>{
     let mut passed_filter: Vec<&Record> =  Vec::new(); 
@@ -122,14 +239,13 @@ fn filter_alignments<'a>(all_maps:&'a Vec<Record>, args: &Cli) ->  Vec<&'a Recor
     return passed_filter; 
 }
 
-fn determine_break(clust: &mut Vec<&Record>, args: &Cli, read_counts: &mut Vec<u64>, break_counts: &mut Vec<u64>, header:&HeaderView )-> Vec<Breakpoint> {
+fn determine_break(clust: &mut Vec<&Record>, args: &Cli, res: &Resources, summary: &mut Summary, header:&HeaderView, seq: &[u8])-> Vec<Breakpoint> {
     //sort by start location of aligment in read 
     let read_length = get_read_len(&clust[0]); 
 
     clust.sort_by_key(|rec| query_loc(rec));
     
     let mut out: Vec<Breakpoint> = Vec::new(); 
-    let mut labels: Vec<u32> = vec![0; 3];  //[fold, chim, pass count]
     
 
     //check every concurrent alignment in a read with n split alignments 
@@ -173,25 +289,34 @@ fn determine_break(clust: &mut Vec<&Record>, args: &Cli, read_counts: &mut Vec<u
         
         let mut break_info = Breakpoint{b1_chr: chr.to_string() ,b1_loc: loc, directions: directions,
              b2_chr: next_chr.to_string(), b2_loc: next_loc,
-             mapq: mapq, read_id: read_id, read_len: read_length, label: None, r1_loc: (read_length - query_end(cur)), r2_loc: query_loc(next)};  
+             b1_chr_len: header.target_len(cur.tid() as u32).unwrap_or(0) as i64, b2_chr_len: header.target_len(next.tid() as u32).unwrap_or(0) as i64,
+             read_info: res.seq_summary.as_ref().and_then(|s| s.get(&read_id)).cloned(),
+             mapq: mapq, read_id: read_id, read_len: read_length, label: None, r1_loc: (read_length - query_end(cur)), r2_loc: query_loc(next),
+             b1_ref: (cur.pos(), cur.reference_end()), b2_ref: (next.pos(), next.reference_end()),
+             b1_arm_len: read_length - query_loc(cur) - query_end(cur), b2_arm_len: read_length - query_loc(next) - query_end(next), ..Default::default()};  
+        break_info.arm_overlap = arm_overlap(&break_info); 
         
         //let rlen = clust[i][1].parse::<f32>().expect("invalid int in field 2"); 
         //let rbreak = clust[i][3].parse::<f32>().expect("invalid int in field 4"); 
 
+        //unaligned read sequence between the two segments
+        if let Some(adapters) = &res.adapters {
+            let (start, end) = (break_info.r1_loc as usize, break_info.r2_loc as usize); 
+            if start < end && seq.len() == read_length as usize {
+                break_info.adapter = adapters.find(&seq[start..end], args.adapter_mismatches).map(String::from); 
+            }
+        }
+
         //get artifact or pass classification 
-        let label = classify_break( &break_info, args, break_counts); 
+        annotate_break(&mut break_info, args, res); 
+        let label = classify_break( &break_info, args, res, &mut summary.break_counts); 
 
-        match label.as_str() {
-            "Foldback" => labels[0] += 1,
-            "Chimeric" => labels[1] += 1,
-            _ => labels[2] += 1,
-        }
         
         break_info.label = Some(label); 
         out.push(break_info); 
         
     }
-    read_level_class(&labels, read_counts); 
+    read_level_class(&out, args, &mut summary.read_counts); 
     return out; 
 }
 
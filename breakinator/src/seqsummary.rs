@@ -0,0 +1,50 @@
+use std::{collections::HashMap, io::{self, BufRead, BufReader}};
+use rust_htslib::bgzf;
+
+// per read fields of an ONT sequencing_summary.txt, NA when the column is missing
+#[derive(Clone)]
+pub struct ReadInfo {
+    pub channel: String,
+    pub mux: String,
+    pub start_time: String,
+    pub duration: String,
+    pub end_reason: String,
+    pub mean_qscore: String,
+}
+
+// sequencing summary given with --seq-summary keyed by read_id
+pub struct SeqSummary {
+    reads: HashMap<String, ReadInfo>,
+}
+
+impl SeqSummary {
+    //read a sequencing_summary.txt (plain or bgzipped), columns are found by name in the header line
+    pub fn load(path: &str) -> io::Result<SeqSummary> {
+        let reader = bgzf::Reader::from_path(path).map_err(io::Error::other)?;
+        let mut lines = BufReader::new(reader).lines();
+        let header = match lines.next() {
+            Some(line) => line?,
+            None => return Ok(SeqSummary{reads: HashMap::new()}),
+        };
+        let columns: Vec<&str> = header.split('\t').collect();
+        let col = |name: &str| columns.iter().position(|c| *c == name);
+        let read_id = col("read_id").ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "no read_id column in sequencing summary"))?;
+        let fields = [col("channel"), col("mux"), col("start_time"), col("duration"), col("end_reason"), col("mean_qscore_template")];
+
+        let mut reads = HashMap::new();
+        for line in lines {
+            let line = line?;
+            let values: Vec<&str> = line.split('\t').collect();
+            let get = |i: Option<usize>| i.and_then(|i| values.get(i)).map_or(String::from("NA"), |v| v.to_string());
+            if let Some(id) = values.get(read_id) {
+                reads.insert(id.to_string(), ReadInfo{channel: get(fields[0]), mux: get(fields[1]), start_time: get(fields[2]),
+                    duration: get(fields[3]), end_reason: get(fields[4]), mean_qscore: get(fields[5])});
+            }
+        }
+        Ok(SeqSummary{reads})
+    }
+
+    pub fn get(&self, read_id: &str) -> Option<&ReadInfo> {
+        self.reads.get(read_id)
+    }
+}
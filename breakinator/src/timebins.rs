@@ -0,0 +1,126 @@
+use std::{collections::BTreeMap, fs::File, io::{self, BufWriter, Write}};
+use crate::{Breakpoint, Cli, Counts};
+use crate::seqsummary::ReadInfo;
+
+//seconds since the epoch of an ISO 8601 timestamp such as the st:Z tag of ONT reads (2023-05-12T10:23:45.123+00:00)
+pub fn parse_timestamp(ts: &str) -> Option<f64> {
+    let (date, time) = ts.split_once('T')?;
+    let mut ymd = date.split('-').map(|v| v.parse::<i64>());
+    let (y, m, d) = (ymd.next()?.ok()?, ymd.next()?.ok()?, ymd.next()?.ok()?);
+
+    //time zone offset after the seconds, if any
+    let (clock, offset) = match time.find(['Z', '+', '-']) {
+        Some(i) => (&time[..i], &time[i..]),
+        None => (time, ""),
+    };
+    let mut hms = clock.split(':');
+    let (h, min) = (hms.next()?.parse::<f64>().ok()?, hms.next()?.parse::<f64>().ok()?);
+    let s = hms.next().map_or(Some(0.0), |s| s.parse::<f64>().ok())?;
+    let offset_secs = match offset {
+        "" | "Z" => 0.0,
+        _ => {
+            //offsets are given as +HH:MM, +HHMM or +HH
+            let sign = if offset.starts_with('-') { -1.0 } else { 1.0 };
+            let digits = offset[1..].replacen(':', "", 1);
+            if !(digits.len() == 2 || digits.len() == 4) || !digits.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            let (oh, om) = digits.split_at(2);
+            sign * (oh.parse::<f64>().ok()? * 3600.0 + om.parse::<f64>().unwrap_or(0.0) * 60.0)
+        }
+    };
+
+    //days since 1970-01-01 in the proleptic Gregorian calendar
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((m + 9) % 12) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+    Some(days as f64 * 86400.0 + h * 3600.0 + min * 60.0 + s - offset_secs)
+}
+
+//bin of --bin-minutes the read start time falls in
+pub fn time_bin(start: f64, args: &Cli) -> i64 {
+    (start / (args.bin_minutes as f64 * 60.0)).floor() as i64
+}
+
+// reads binned by start time, kept apart by the time base of their start time: seconds since the epoch
+// from the st:Z tag or seconds since the start of the run from the sequencing summary
+#[derive(Default)]
+pub struct TimeBins {
+    bins: [BTreeMap<i64, Counts>; 2],
+}
+
+impl TimeBins {
+    pub fn new() -> TimeBins {
+        TimeBins::default()
+    }
+
+    //add a read by the st:Z tag, or the sequencing summary start time without one, reads without a start time are left out
+    pub fn add_read(&mut self, st_tag: &str, info: Option<&ReadInfo>, breaks: &[Breakpoint], args: &Cli) {
+        let (start, base) = match parse_timestamp(st_tag) {
+            Some(start) => (start, 0),
+            None => match info.and_then(|i| i.start_time.parse::<f64>().ok()) {
+                Some(start) => (start, 1),
+                None => return,
+            },
+        };
+        self.bins[base].entry(time_bin(start, args)).or_default().add_read(breaks, args);
+    }
+
+    //write the read and breakpoint artifact rates of each bin, with minutes since the first bin and empty bins included
+    //the two time bases cannot be put on one axis, so only the one covering more reads is written
+    pub fn write(&self, path: &str, args: &Cli) -> io::Result<()> {
+        let reads = |bins: &BTreeMap<i64, Counts>| bins.values().map(|c| c.reads_pass_filter).sum::<u64>();
+        let (kept, dropped) = if reads(&self.bins[0]) >= reads(&self.bins[1]) { (0, 1) } else { (1, 0) };
+        if reads(&self.bins[dropped]) > 0 {
+            let names = ["st:Z timestamps", "sequencing summary start times"];
+            eprintln!("Warning: {} reads with {} are left out of --time-bins, which uses the {} of the other {} reads",
+                reads(&self.bins[dropped]), names[dropped], names[kept], reads(&self.bins[kept]));
+        }
+        let bins = &self.bins[kept];
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "#Minutes\tReads_passed\tFold_reads\tFold_reads%\tFold_breaks\tFold_breaks%\tChim_reads\tChim_reads%\tChim_breaks\tChim_breaks%")?;
+        let (first, last) = match (bins.keys().next(), bins.keys().next_back()) {
+            (Some(first), Some(last)) => (*first, *last),
+            _ => return Ok(()),
+        };
+        let empty = Counts::new();
+        for bin in first..=last {
+            let counts = bins.get(&bin).unwrap_or(&empty);
+            writeln!(writer, "{}\t{}", (bin - first) * args.bin_minutes, counts.stats().join("\t"))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_timestamp;
+
+    #[test]
+    fn timestamps_in_utc() {
+        assert_eq!(parse_timestamp("1970-01-01T00:00:00Z"), Some(0.0));
+        assert_eq!(parse_timestamp("2024-02-29T00:00:00Z"), Some(1709164800.0));
+        assert_eq!(parse_timestamp("2023-05-12T10:23:45.500+00:00"), Some(1683887025.5));
+        assert_eq!(parse_timestamp("1969-12-31T23:00:00"), Some(-3600.0));
+    }
+
+    #[test]
+    fn timestamp_offsets() {
+        let utc = parse_timestamp("2023-05-12T10:23:45Z");
+        assert_eq!(parse_timestamp("2023-05-12T12:23:45+02:00"), utc);
+        assert_eq!(parse_timestamp("2023-05-12T12:23:45+0200"), utc);
+        assert_eq!(parse_timestamp("2023-05-12T12:23:45+02"), utc);
+        assert_eq!(parse_timestamp("2023-05-12T05:53:45-04:30"), utc);
+    }
+
+    #[test]
+    fn invalid_timestamps() {
+        assert_eq!(parse_timestamp("NA"), None);
+        assert_eq!(parse_timestamp("2023-05-12"), None);
+        assert_eq!(parse_timestamp("2023-05-12T10:23:45+2"), None);
+        assert_eq!(parse_timestamp("2023-05-12T10:23:45+02:0x"), None);
+    }
+}
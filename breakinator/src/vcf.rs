@@ -0,0 +1,118 @@
+use std::{collections::HashMap, fs::File, io::{BufRead, BufReader, BufWriter, Write}, process};
+use rust_htslib::bgzf;
+use crate::cli::AnnotateVcfArgs;
+use crate::read_class;
+
+pub fn annotate_vcf(args: &AnnotateVcfArgs) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(frac) = args.filter_frac {
+        if !(0.0..=1.0).contains(&frac) {
+            eprintln!( "error: `--filter-frac {}` is out of range; must be between 0.0 and 1.0 (inclusive)", frac );
+            process::exit(1);
+        }
+    }
+
+    let read_classes = load_read_classes(&args.breaks)?;
+
+    //bgzf reader handles both plain and bgzipped VCF
+    let reader = BufReader::new(bgzf::Reader::from_path(&args.vcf)?);
+    let mut writer = BufWriter::new(File::create(&args.out)?);
+
+    let tag = format!("{}=", args.rnames_tag);
+    let mut num_calls: u64 = 0;
+    let mut num_filtered: u64 = 0;
+
+    for line in reader.lines() {
+        let line = line?;
+        if line.starts_with("##") {
+            writeln!(writer, "{}", line)?;
+            continue;
+        }
+        if line.starts_with('#') {
+            //add the new header lines just before the column header
+            writeln!(writer, "##INFO=<ID=BKN_FOLD,Number=1,Type=Integer,Description=\"Number of supporting reads classified as Foldback by breakinator\">")?;
+            writeln!(writer, "##INFO=<ID=BKN_CHIM,Number=1,Type=Integer,Description=\"Number of supporting reads classified as Chimeric by breakinator\">")?;
+            writeln!(writer, "##INFO=<ID=BKN_FRAC,Number=1,Type=Float,Description=\"Fraction of supporting reads classified as Foldback or Chimeric by breakinator\">")?;
+            if let Some(frac) = args.filter_frac {
+                writeln!(writer, "##FILTER=<ID=BKN_ARTIFACT,Description=\"At least {} of supporting reads classified as artifacts by breakinator\">", frac)?;
+            }
+            writeln!(writer, "{}", line)?;
+            continue;
+        }
+
+        let mut fields: Vec<String> = line.split('\t').map(|f| f.to_string()).collect();
+        if fields.len() < 8 {
+            writeln!(writer, "{}", line)?;
+            continue;
+        }
+        num_calls += 1;
+
+        let rnames = fields[7].split(';').find_map(|f| f.strip_prefix(tag.as_str()));
+        let Some(rnames) = rnames else {
+            //no supporting reads listed, leave the record as is
+            writeln!(writer, "{}", line)?;
+            continue;
+        };
+
+        //count supporting reads by their read level class [fold, chim, pass]
+        let mut support: Vec<u32> = vec![0; 3];
+        for name in rnames.split(',').filter(|n| !n.is_empty()) {
+            match read_classes.get(name) {
+                Some(c) => support[*c] += 1,
+                None => support[2] += 1,
+            }
+        }
+        let total = support.iter().sum::<u32>();
+        let frac = if total > 0 { (support[0] + support[1]) as f32 / total as f32 } else { 0.0 };
+
+        let annotation = format!("BKN_FOLD={};BKN_CHIM={};BKN_FRAC={:.3}", support[0], support[1], frac);
+        if fields[7] == "." {
+            fields[7] = annotation;
+        } else {
+            fields[7] = format!("{};{}", fields[7], annotation);
+        }
+
+        if let Some(min_frac) = args.filter_frac {
+            if total > 0 && frac >= min_frac {
+                num_filtered += 1;
+                if fields[6] == "PASS" || fields[6] == "." {
+                    fields[6] = String::from("BKN_ARTIFACT");
+                } else {
+                    fields[6] = format!("{};BKN_ARTIFACT", fields[6]);
+                }
+            }
+        }
+        writeln!(writer, "{}", fields.join("\t"))?;
+    }
+
+    println!("Annotated {} SV calls with artifact support from {}", num_calls, args.breaks);
+    if args.filter_frac.is_some() {
+        println!("Num calls filtered as BKN_ARTIFACT: {}", num_filtered);
+    }
+    Ok(())
+}
+
+//read level classification [fold, chim, pass] of every read in a breakinator breakpoint output
+fn load_read_classes(path: &str) -> Result<HashMap<String, usize>, Box<dyn std::error::Error>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut labels: HashMap<String, Vec<u32>> = HashMap::new();
+    for line in reader.lines() {
+        let line = line?;
+        if line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() < 8 {
+            continue;
+        }
+        let counts = labels.entry(fields[6].to_string()).or_insert_with(|| vec![0; 3]);
+        match fields[7] {
+            "Foldback" | "Palindromic" => counts[0] += 1,
+            "Chimeric" => counts[1] += 1,
+            //recurrent and contig end artifacts are counted by the shape of their junction
+            "SystematicArtifact" | "ContigEnd" if fields[0] == fields[3] && (fields[2] == "<>" || fields[2] == "><") => counts[0] += 1,
+            "SystematicArtifact" | "ContigEnd" => counts[1] += 1,
+            _ => counts[2] += 1,
+        }
+    }
+    Ok(labels.into_iter().map(|(id, counts)| (id, read_class(&counts))).collect())
+}